@@ -2,7 +2,8 @@
 //! 
 //! This example demonstrates how to use forge-tree programmatically
 
-use forge_tree::Parser;
+use forge_tree::parser::ProjectStructure;
+use forge_tree::{Generator, Parser};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Forge-Tree Basic Usage Example");
@@ -26,10 +27,25 @@ example-project/
     let structure = parser.parse(structure_text)?;
     
     println!("🌳 Root: {}", structure.root);
-    println!("📁 Items: {}", count_items(&structure.items));
+    println!("📁 Items: {}", structure.stats().total());
+    
+    // Structures can also be built in code instead of parsed
+    let built = ProjectStructure::builder("built-project")
+        .dir("src", |d| d.file("main.rs").content("fn main() {}\n"))
+        .file("Cargo.toml").template("[package]\nname = \"{{name}}\"\n")
+        .var("name", "built-project")
+        .build()?;
+
+    println!("🔨 Built: {} ({} items)", built.root, built.stats().total());
+
+    // Rendering in memory previews the output without touching the disk
+    let rendered = Generator::new().render(&built)?;
+    for file in &rendered.files {
+        println!("   {} ({} bytes)", file.path.display(), file.contents.len());
+    }
     
     // Generate the project (commented out to avoid creating files in example)
-    // let generator = Generator::new().with_verbose(true);
+    // let generator = Generator::new().with_reporter(Arc::new(TerminalReporter::new(true)));
     // generator.generate(&structure, "./example-output")?;
     
     println!("✅ Example completed successfully!");
@@ -37,12 +53,3 @@ example-project/
     
     Ok(())
 }
-
-// Helper function to count items
-fn count_items(items: &[forge_tree::parser::StructureItem]) -> usize {
-    let mut count = items.len();
-    for item in items {
-        count += count_items(&item.children);
-    }
-    count
-}
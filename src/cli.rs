@@ -1,17 +1,36 @@
 //! Command-line interface implementation for forge-tree
 //! 
 //! This module handles all CLI interactions including parsing arguments,
-//! subcommands, and orchestrating the forge and validate operations.
+//! subcommands, and orchestrating the forge, validate, show, resolve, diff, check, update and undo operations.
 
-use crate::{Generator, Parser, Result};
+use crate::config::Config;
+use crate::generator::{
+    prompt, ArchiveBackend, ConflictPolicy, GenerationSummary, JsonLinesReporter, PlainReporter, ProgressReporter,
+    SilentReporter, TerminalReporter,
+};
+use crate::parser::{Duplicate, DuplicatePolicy, ProjectStructure, TreeWriter};
+use crate::git::GitOptions;
+use crate::hooks::{FailurePolicy, HookStage};
+use crate::diff::{diff, Difference, DifferenceKind};
+use crate::report::{self, DiffReport, ForgeReport, ValidateReport};
+use crate::scanner::Scanner;
+use crate::lockfile::{Lockfile, LOCKFILE_NAME};
+use crate::undo::Undo;
+use crate::update::Update;
+use crate::variables::VarType;
+use crate::{ForgeTreeError, Generator, Parser, Result};
 use clap::{Arg, ArgMatches, Command};
 use colored::*;
 use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::Arc;
 
 pub struct Cli;
 
 impl Cli {
     /// Creates the CLI command structure with all subcommands and arguments
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Command {
         Command::new("forge-tree")
             .version(env!("CARGO_PKG_VERSION"))
@@ -35,12 +54,26 @@ impl Cli {
                             .help("Output directory (default: current directory)")
                             .default_value(".")
                     )
+                    .arg(
+                        Arg::new("archive")
+                            .long("archive")
+                            .help("Write the project into an archive (.tar, .tar.gz, .tgz or .zip) instead of a directory")
+                            .conflicts_with("output")
+                    )
                     .arg(
                         Arg::new("force")
                             .short('f')
                             .long("force")
-                            .help("Force overwrite existing files")
+                            .help("Force overwrite existing files (same as --on-conflict=overwrite)")
                             .action(clap::ArgAction::SetTrue)
+                            .conflicts_with("on-conflict")
+                    )
+                    .arg(
+                        Arg::new("on-conflict")
+                            .long("on-conflict")
+                            .help("How to handle files that already exist")
+                            .value_parser(["error", "skip", "overwrite", "backup", "prompt"])
+                            .default_value("error")
                     )
                     .arg(
                         Arg::new("verbose")
@@ -49,12 +82,87 @@ impl Cli {
                             .help("Verbose output showing each file/directory creation")
                             .action(clap::ArgAction::SetTrue)
                     )
+                    .arg(
+                        Arg::new("quiet")
+                            .short('q')
+                            .long("quiet")
+                            .help("Print nothing but errors")
+                            .action(clap::ArgAction::SetTrue)
+                            .conflicts_with_all(["verbose", "progress"])
+                    )
+                    .arg(
+                        Arg::new("progress")
+                            .long("progress")
+                            .help("How to report progress; auto shows a bar on a terminal and plain lines otherwise")
+                            .value_parser(["auto", "bar", "plain", "json"])
+                            .default_value("auto")
+                    )
                     .arg(
                         Arg::new("variable")
                             .long("var")
                             .help("Set template variables (format: key=value)")
                             .action(clap::ArgAction::Append)
                     )
+                    .arg(
+                        Arg::new("dry-run")
+                            .long("dry-run")
+                            .help("Show what would be created and which items are excluded, without writing anything")
+                            .action(clap::ArgAction::SetTrue)
+                    )
+                    .arg(
+                        Arg::new("lock")
+                            .long("lock")
+                            .help("Write a .forge-tree.lock manifest of what was generated into the project root")
+                            .action(clap::ArgAction::SetTrue)
+                    )
+                    .arg(
+                        Arg::new("jobs")
+                            .short('j')
+                            .long("jobs")
+                            .help("Render and write files on this many worker threads (0 = one per CPU)")
+                            .value_parser(clap::value_parser!(usize))
+                            .default_value("1")
+                    )
+                    .arg(
+                        Arg::new("no-input")
+                            .long("no-input")
+                            .help("Never prompt; fail if template variables are missing or hooks need confirming")
+                            .action(clap::ArgAction::SetTrue)
+                    )
+                    .arg(
+                        Arg::new("no-hooks")
+                            .long("no-hooks")
+                            .help("Don't run the structure's pre_generate and post_generate hooks")
+                            .action(clap::ArgAction::SetTrue)
+                    )
+                    .arg(
+                        Arg::new("git")
+                            .long("git")
+                            .help("Initialise a git repository in the project root and write the structure's .gitignore")
+                            .action(clap::ArgAction::SetTrue)
+                    )
+                    .arg(
+                        Arg::new("git-commit")
+                            .long("git-commit")
+                            .help("Like --git, and commit everything generated")
+                            .action(clap::ArgAction::SetTrue)
+                    )
+                    .arg(
+                        Arg::new("git-message")
+                            .long("git-message")
+                            .value_name("TEMPLATE")
+                            .help("Initial commit message; may use template variables")
+                            .requires("git-commit")
+                    )
+                    .arg(
+                        Arg::new("git-author")
+                            .long("git-author")
+                            .value_name("TEMPLATE")
+                            .help("Initial commit author as \"Name <email>\"; may use template variables")
+                            .requires("git-commit")
+                    )
+                    .arg(Self::duplicate_policy_arg())
+                    .arg(Self::format_arg())
             )
             .subcommand(
                 Command::new("validate")
@@ -65,7 +173,156 @@ impl Cli {
                             .required(true)
                             .index(1)
                     )
+                    .arg(Self::duplicate_policy_arg())
+                    .arg(Self::format_arg())
+            )
+            .subcommand(
+                Command::new("show")
+                    .about("Print the parsed structure and its declared variables")
+                    .arg(
+                        Arg::new("input")
+                            .help("Input file to show")
+                            .required(true)
+                            .index(1)
+                    )
+                    .arg(Self::duplicate_policy_arg())
+            )
+            .subcommand(
+                Command::new("resolve")
+                    .about("Print the flattened structure after applying includes and extends")
+                    .arg(
+                        Arg::new("input")
+                            .help("Input file to resolve")
+                            .required(true)
+                            .index(1)
+                    )
+                    .arg(Self::duplicate_policy_arg())
             )
+            .subcommand(Self::diff_command("diff", "Show how a directory differs from a structure file"))
+            .subcommand(Self::diff_command(
+                "check",
+                "Like diff, but exit with an error if the directory doesn't match the structure",
+            ))
+            .subcommand(
+                Command::new("update")
+                    .about("Re-render a forged project's structure and merge the changes into it")
+                    .arg(
+                        Arg::new("project")
+                            .help("Project root containing .forge-tree.lock")
+                            .required(true)
+                            .index(1)
+                    )
+                    .arg(
+                        Arg::new("input")
+                            .help("Structure file to update from (default: the one recorded in .forge-tree.lock)")
+                            .index(2)
+                    )
+                    .arg(
+                        Arg::new("variable")
+                            .long("var")
+                            .help("Set or change template variables (format: key=value)")
+                            .action(clap::ArgAction::Append)
+                    )
+                    .arg(
+                        Arg::new("conflict")
+                            .long("conflict")
+                            .help("Write conflicts as markers in the file, or as a .rej patch next to it")
+                            .value_parser(["markers", "reject"])
+                            .default_value("markers")
+                    )
+                    .arg(
+                        Arg::new("dry-run")
+                            .long("dry-run")
+                            .help("Show what would change without writing anything")
+                            .action(clap::ArgAction::SetTrue)
+                    )
+                    .arg(
+                        Arg::new("no-input")
+                            .long("no-input")
+                            .help("Never prompt; fail if template variables are missing")
+                            .action(clap::ArgAction::SetTrue)
+                    )
+                    .arg(Self::duplicate_policy_arg())
+            )
+            .subcommand(
+                Command::new("undo")
+                    .about("Remove what a forge created, using the project's .forge-tree.lock")
+                    .arg(
+                        Arg::new("project")
+                            .help("Project root containing .forge-tree.lock")
+                            .required(true)
+                            .index(1)
+                    )
+                    .arg(
+                        Arg::new("dry-run")
+                            .long("dry-run")
+                            .help("Show what would be removed without deleting anything")
+                            .action(clap::ArgAction::SetTrue)
+                    )
+                    .arg(
+                        Arg::new("force")
+                            .short('f')
+                            .long("force")
+                            .help("Also remove files that were modified since they were forged")
+                            .action(clap::ArgAction::SetTrue)
+                    )
+            )
+    }
+
+    /// Shared `--on-duplicate` argument for subcommands that parse structures
+    fn duplicate_policy_arg() -> Arg {
+        Arg::new("on-duplicate")
+            .long("on-duplicate")
+            .help("How to handle sibling entries with the same name")
+            .value_parser(["error", "merge", "last-wins"])
+            .default_value("error")
+    }
+
+    /// The `diff` and `check` subcommands, which only differ in their exit status
+    fn diff_command(name: &'static str, about: &'static str) -> Command {
+        Command::new(name)
+            .about(about)
+            .arg(
+                Arg::new("input")
+                    .help("Structure file describing the expected layout")
+                    .required(true)
+                    .index(1)
+            )
+            .arg(
+                Arg::new("directory")
+                    .help("Project directory to compare (the structure's root)")
+                    .required(true)
+                    .index(2)
+            )
+            .arg(
+                Arg::new("content")
+                    .long("content")
+                    .help("Also compare the contents of files that have content or a template")
+                    .action(clap::ArgAction::SetTrue)
+            )
+            .arg(
+                Arg::new("ignore")
+                    .long("ignore")
+                    .help("Glob pattern for paths to ignore (repeatable), e.g. 'target' or 'docs/*.md'")
+                    .action(clap::ArgAction::Append)
+            )
+            .arg(
+                Arg::new("variable")
+                    .long("var")
+                    .help("Set template variables (format: key=value)")
+                    .action(clap::ArgAction::Append)
+            )
+            .arg(Self::duplicate_policy_arg())
+            .arg(Self::format_arg())
+    }
+
+    /// Shared `--format` argument for subcommands with a machine-readable report
+    fn format_arg() -> Arg {
+        Arg::new("format")
+            .long("format")
+            .help("Output format; json prints a single report document on stdout")
+            .value_parser(["text", "json"])
+            .default_value("text")
     }
 
     /// Main entry point for CLI execution
@@ -74,6 +331,12 @@ impl Cli {
         match matches.subcommand() {
             Some(("forge", sub_matches)) => Self::handle_forge(sub_matches),
             Some(("validate", sub_matches)) => Self::handle_validate(sub_matches),
+            Some(("show", sub_matches)) => Self::handle_show(sub_matches),
+            Some(("resolve", sub_matches)) => Self::handle_resolve(sub_matches),
+            Some(("diff", sub_matches)) => Self::handle_diff(sub_matches, false),
+            Some(("check", sub_matches)) => Self::handle_diff(sub_matches, true),
+            Some(("update", sub_matches)) => Self::handle_update(sub_matches),
+            Some(("undo", sub_matches)) => Self::handle_undo(sub_matches),
             _ => unreachable!(), // clap ensures this won't happen due to subcommand_required(true)
         }
     }
@@ -81,12 +344,55 @@ impl Cli {
     /// Handler for the `forge` subcommand
     /// Parses the structure file and generates the project directory
     fn handle_forge(matches: &ArgMatches) -> Result<()> {
+        if !Self::json_output(matches) {
+            return Self::forge(matches, Self::progress_reporter(matches)).map(|_| ());
+        }
+
+        let input_file = matches.get_one::<String>("input").unwrap();
+        let output = matches.get_one::<String>("archive")
+            .or_else(|| matches.get_one::<String>("output"))
+            .unwrap();
+        let result = Self::forge(matches, Arc::new(SilentReporter));
+        report::print(&ForgeReport::new(input_file, output, matches.get_flag("dry-run"), &result))?;
+        result.map(|_| ())
+    }
+
+    /// Pick a reporter from `--quiet`, `--progress` and whether stdout is a terminal
+    fn progress_reporter(matches: &ArgMatches) -> Arc<dyn ProgressReporter> {
+        let verbose = matches.get_flag("verbose");
+        if matches.get_flag("quiet") {
+            return Arc::new(SilentReporter);
+        }
+
+        match matches.get_one::<String>("progress").map(String::as_str) {
+            Some("bar") => Arc::new(TerminalReporter::new(verbose)),
+            Some("plain") => Arc::new(PlainReporter::new(verbose)),
+            Some("json") => Arc::new(JsonLinesReporter),
+            _ if std::io::stdout().is_terminal() => Arc::new(TerminalReporter::new(verbose)),
+            _ => Arc::new(PlainReporter::new(verbose)),
+        }
+    }
+
+    /// Whether forge prints its own human-readable messages besides the reporter's
+    fn human_output(matches: &ArgMatches) -> bool {
+        !Self::json_output(matches)
+            && !matches.get_flag("quiet")
+            && matches.get_one::<String>("progress").map(String::as_str) != Some("json")
+    }
+
+    /// Parse and generate, reporting progress to `reporter`
+    fn forge(matches: &ArgMatches, reporter: Arc<dyn ProgressReporter>) -> Result<GenerationSummary> {
         // Extract command line arguments
         let input_file = matches.get_one::<String>("input").unwrap();
         let output_dir = matches.get_one::<String>("output").unwrap();
-        let force = matches.get_flag("force");
-        let verbose = matches.get_flag("verbose");
+        let conflict_policy = if matches.get_flag("force") {
+            ConflictPolicy::Overwrite
+        } else {
+            matches.get_one::<String>("on-conflict").unwrap().parse()?
+        };
+        let verbose = matches.get_flag("verbose") && Self::human_output(matches);
         let variables = Self::parse_variables(matches);
+        let duplicate_policy = Self::duplicate_policy(matches)?;
 
         // Show parsing progress if verbose mode is enabled
         if verbose {
@@ -94,7 +400,7 @@ impl Cli {
         }
 
         // Parse the structure file
-        let parser = Parser::new();
+        let parser = Parser::new().with_duplicate_policy(duplicate_policy);
         let mut structure = parser.parse_file(input_file)?;
 
         // Merge user-provided template variables with parsed structure
@@ -103,39 +409,408 @@ impl Cli {
         // Display structure summary in verbose mode
         if verbose {
             println!("{} Root: {}", "🌳".green(), structure.root);
-            println!("{} Items: {}", "📁".blue(), Self::count_total_items(&structure.items));
+            println!("{} Items: {}", "📁".blue(), structure.stats().total());
         }
 
+        // Only prompt for missing variables when a person is at the terminal
+        let interactive = !matches.get_flag("no-input") && std::io::stdin().is_terminal();
+        let config = Config::load()?;
+
+        // Hooks run commands, so ask before running any from outside the trusted directories
+        let dry_run = matches.get_flag("dry-run");
+        let hooks = !matches.get_flag("no-hooks") && (dry_run || Self::confirm_hooks(&structure, &config, interactive)?);
+
         // Create and configure the generator with CLI flags
         let generator = Generator::new()
-            .with_verbose(verbose)
-            .with_force_override(force); // Pass the --force flag to enable overwriting
+            .with_reporter(reporter)
+            .with_conflict_policy(conflict_policy)
+            .with_dry_run(dry_run)
+            .with_lockfile(matches.get_flag("lock"))
+            .with_jobs(*matches.get_one::<usize>("jobs").unwrap())
+            .with_hooks(hooks)
+            .with_git(Self::git_options(matches))
+            .with_source(input_file)
+            .with_interactive(interactive)
+            .with_variable_defaults(config.variables);
 
         // Execute the project generation
-        generator.generate(&structure, output_dir)?;
+        match matches.get_one::<String>("archive") {
+            Some(archive) if !dry_run => {
+                let backend = Arc::new(ArchiveBackend::create(archive)?);
+                generator.generate_into(&structure, backend)
+            }
+            _ => generator.generate(&structure, output_dir),
+        }
+    }
 
-        Ok(())
+    /// Git options from `--git`, `--git-commit`, `--git-message` and `--git-author`
+    fn git_options(matches: &ArgMatches) -> Option<GitOptions> {
+        let commit = matches.get_flag("git-commit");
+        if !commit && !matches.get_flag("git") {
+            return None;
+        }
+
+        let mut options = GitOptions::new().with_commit(commit);
+        if let Some(message) = matches.get_one::<String>("git-message") {
+            options = options.with_message(message);
+        }
+        if let Some(author) = matches.get_one::<String>("git-author") {
+            options = options.with_author(author);
+        }
+        Some(options)
+    }
+
+    /// Whether the structure's hooks may run, asking about those from untrusted files
+    fn confirm_hooks(structure: &ProjectStructure, config: &Config, interactive: bool) -> Result<bool> {
+        let untrusted = structure.hooks.untrusted_sources(&config.trusted);
+        if untrusted.is_empty() {
+            return Ok(true);
+        }
+
+        let sources: Vec<String> = untrusted.iter().map(|source| source.display().to_string()).collect();
+        if !interactive {
+            return Err(ForgeTreeError::Hook(format!(
+                "{} declares hooks but isn't in a trusted directory (add it to `trusted` in {}, or pass --no-hooks)",
+                sources.join(", "),
+                Config::default_path().map(|path| path.display().to_string()).unwrap_or_else(|| "the config file".to_string())
+            )));
+        }
+
+        eprintln!("{} {} wants to run:", "⚠️".yellow(), sources.join(", "));
+        for hook in structure.hooks.iter().filter(|hook| !hook.is_trusted(&config.trusted)) {
+            eprintln!("    {}", hook.command_line());
+        }
+        let answer = prompt::ask("Run these hooks? [y/N]:")?;
+        let confirmed = matches!(answer.as_str(), "y" | "yes");
+        if !confirmed {
+            eprintln!("{} Continuing without hooks", "⚠️".yellow());
+        }
+        Ok(confirmed)
     }
 
     /// Handler for the `validate` subcommand
     /// Checks structure file syntax without creating any files
     fn handle_validate(matches: &ArgMatches) -> Result<()> {
         let input_file = matches.get_one::<String>("input").unwrap();
+        let duplicate_policy = Self::duplicate_policy(matches)?;
+        let parser = Parser::new().with_duplicate_policy(duplicate_policy);
+
+        if Self::json_output(matches) {
+            let (duplicates, result) = Self::validate(&parser, input_file);
+            report::print(&ValidateReport::new(input_file, &duplicates, duplicate_policy, &result))?;
+            return result.map(|_| ());
+        }
 
         println!("{} Validating: {}", "🔍".cyan(), input_file);
 
-        // Parse the structure file (validation happens during parsing)
-        let parser = Parser::new();
-        let structure = parser.parse_file(input_file)?;
+        // Report every duplicate sibling before the policy is applied
+        let (duplicates, result) = Self::validate(&parser, input_file);
+        for duplicate in &duplicates {
+            if duplicate_policy == DuplicatePolicy::Error {
+                println!("  {} Duplicate entry {}", "❌".red(), duplicate);
+            } else {
+                println!("  {} Duplicate entry {}", "⚠️".yellow(), duplicate);
+            }
+        }
+        let structure = result?;
 
         // Show validation results
         println!("{} Structure is valid!", "✅".green());
         println!("  {} Root: {}", "🌳".green(), structure.root);
-        println!("  {} Total items: {}", "📊".blue(), Self::count_total_items(&structure.items));
+        println!("  {} Total items: {}", "📊".blue(), structure.stats().total());
+        Self::print_declarations(&structure);
+        Self::print_hooks(&structure);
 
         Ok(())
     }
 
+    /// Parse a structure file, also returning the duplicate siblings found before resolution
+    fn validate(parser: &Parser, input_file: &str) -> (Vec<Duplicate>, Result<ProjectStructure>) {
+        // Syntax validation happens during parsing
+        let (duplicates, result) = parser.parse_file_with_duplicates(input_file);
+        let result = match result {
+            // Summarise every duplicate instead of naming only the first one
+            Err(ForgeTreeError::DuplicateEntry(_)) if parser.duplicate_policy() == DuplicatePolicy::Error => {
+                Err(ForgeTreeError::DuplicateEntry(format!(
+                    "{} duplicate entries found (use --on-duplicate=merge or last-wins to resolve them)",
+                    duplicates.len()
+                )))
+            }
+            result => result,
+        };
+        (duplicates, result)
+    }
+
+    /// Handler for the `show` subcommand
+    /// Prints the structure as the parser understood it, followed by its variable schema
+    fn handle_show(matches: &ArgMatches) -> Result<()> {
+        let input_file = matches.get_one::<String>("input").unwrap();
+        let duplicate_policy = Self::duplicate_policy(matches)?;
+
+        let parser = Parser::new().with_duplicate_policy(duplicate_policy);
+        let structure = parser.parse_file(input_file)?;
+
+        print!("{}", TreeWriter::new().with_front_matter(false).write(&structure)?);
+        Self::print_declarations(&structure);
+        Self::print_hooks(&structure);
+
+        Ok(())
+    }
+
+    /// Handler for the `resolve` subcommand
+    /// Prints the flattened structure in the tree format, ready to be forged or reviewed
+    fn handle_resolve(matches: &ArgMatches) -> Result<()> {
+        let input_file = matches.get_one::<String>("input").unwrap();
+        let duplicate_policy = Self::duplicate_policy(matches)?;
+
+        let parser = Parser::new().with_duplicate_policy(duplicate_policy);
+        let structure = parser.parse_file(input_file)?;
+
+        print!("{}", TreeWriter::new().write(&structure)?);
+        Ok(())
+    }
+
+    /// Handler for the `diff` and `check` subcommands
+    /// Reports missing, extra and mismatched entries; `check` fails if there are any
+    fn handle_diff(matches: &ArgMatches, check: bool) -> Result<()> {
+        let input_file = matches.get_one::<String>("input").unwrap();
+        let directory = matches.get_one::<String>("directory").unwrap();
+        let result = Self::compare(matches);
+
+        if Self::json_output(matches) {
+            let command = if check { "check" } else { "diff" };
+            report::print(&DiffReport::new(command, input_file, directory, &result))?;
+        }
+        let differences = result?;
+
+        if !Self::json_output(matches) {
+            for difference in &differences {
+                let expected = difference.expected.map(|kind| kind.to_string()).unwrap_or_default();
+                let actual = difference.actual.map(|kind| kind.to_string()).unwrap_or_default();
+                match difference.kind {
+                    DifferenceKind::Missing => {
+                        println!("{} {} ({})", "Missing".red().bold(), difference.path, expected);
+                    }
+                    DifferenceKind::Extra => {
+                        println!("{} {} ({})", "Extra".yellow().bold(), difference.path, actual);
+                    }
+                    DifferenceKind::TypeMismatch => println!(
+                        "{} {} (expected {}, found {})", "Mismatch".red().bold(), difference.path, expected, actual
+                    ),
+                    DifferenceKind::ContentMismatch => {
+                        println!("{} {}", "Changed".magenta().bold(), difference.path);
+                    }
+                    DifferenceKind::ModeMismatch => println!(
+                        "{} {} (expected mode {}, found {})",
+                        "Mode".magenta().bold(),
+                        difference.path,
+                        difference.expected_mode.as_deref().unwrap_or_default(),
+                        difference.actual_mode.as_deref().unwrap_or_default()
+                    ),
+                    DifferenceKind::TargetMismatch => println!(
+                        "{} {} (expected -> {}, found -> {})",
+                        "Target".magenta().bold(),
+                        difference.path,
+                        difference.expected_target.as_deref().unwrap_or_default(),
+                        difference.actual_target.as_deref().unwrap_or_default()
+                    ),
+                }
+            }
+
+            if differences.is_empty() {
+                println!("{} {} matches {}", "✅".green(), directory, input_file);
+            } else {
+                println!("\n{} {} differences", "📝".cyan(), differences.len());
+            }
+        }
+
+        if check && !differences.is_empty() {
+            return Err(ForgeTreeError::Mismatch(format!(
+                "{} differences between {} and {}", differences.len(), input_file, directory
+            )));
+        }
+        Ok(())
+    }
+
+    /// Render the structure and compare it with the directory on disk
+    fn compare(matches: &ArgMatches) -> Result<Vec<Difference>> {
+        let input_file = matches.get_one::<String>("input").unwrap();
+        let directory = matches.get_one::<String>("directory").unwrap();
+
+        let parser = Parser::new().with_duplicate_policy(Self::duplicate_policy(matches)?);
+        let mut structure = parser.parse_file(input_file)?;
+        structure.variables.extend(Self::parse_variables(matches));
+
+        // Comparisons are meant for CI, so never prompt
+        let expected = Generator::new()
+            .with_variable_defaults(Config::load()?.variables)
+            .render(&structure)?;
+
+        let ignore = matches.get_many::<String>("ignore").into_iter().flatten();
+        let scanner = Scanner::new().with_ignore(ignore)?;
+        let actual = scanner.scan(directory)?;
+
+        diff(&expected, &actual, Path::new(directory), scanner.ignore(), matches.get_flag("content"))
+    }
+
+    /// Handler for the `update` subcommand
+    /// Merges the newly rendered structure into a previously forged project
+    fn handle_update(matches: &ArgMatches) -> Result<()> {
+        let project = matches.get_one::<String>("project").unwrap();
+        let dry_run = matches.get_flag("dry-run");
+
+        let input_file = match matches.get_one::<String>("input") {
+            Some(input) => input.clone(),
+            None => Lockfile::load(project)?.source
+                .map(|source| source.path)
+                .ok_or_else(|| ForgeTreeError::InvalidPath(format!(
+                    "{} doesn't record a structure file; pass one explicitly", LOCKFILE_NAME
+                )))?,
+        };
+
+        let parser = Parser::new().with_duplicate_policy(Self::duplicate_policy(matches)?);
+        let mut structure = parser.parse_file(&input_file)?;
+        structure.variables.extend(Self::parse_variables(matches));
+
+        let interactive = !matches.get_flag("no-input") && std::io::stdin().is_terminal();
+        let generator = Generator::new()
+            .with_interactive(interactive)
+            .with_variable_defaults(Config::load()?.variables);
+
+        let report = Update::new()
+            .with_dry_run(dry_run)
+            .with_conflict_style(matches.get_one::<String>("conflict").unwrap().parse()?)
+            .with_source(&input_file)
+            .run(&generator, &structure, project)?;
+
+        let lines = [
+            (&report.created, "create", "Created"),
+            (&report.updated, "update", "Updated"),
+            (&report.merged, "merge", "Merged"),
+            (&report.removed, "remove", "Removed"),
+        ];
+        for (paths, would, did) in lines {
+            let label = if dry_run { format!("Would {}", would) } else { did.to_string() };
+            for path in paths {
+                println!("{} {}", label.green().bold(), path);
+            }
+        }
+        for path in &report.kept {
+            println!("{} {} (changed locally; upstream change not applied)", "Kept".yellow().bold(), path);
+        }
+        for path in &report.conflicts {
+            println!("{} {}", "Conflict".red().bold(), path);
+        }
+
+        if report.is_empty() {
+            println!("{} Already up to date", "✅".green());
+        } else if report.conflicts.is_empty() {
+            println!("\n{} Update complete", "✅".green());
+        } else {
+            println!("\n{} {} files have conflicts to resolve", "⚠️".yellow(), report.conflicts.len());
+        }
+
+        Ok(())
+    }
+
+    /// Handler for the `undo` subcommand
+    /// Deletes unmodified forged files and the directories forge created
+    fn handle_undo(matches: &ArgMatches) -> Result<()> {
+        let project = matches.get_one::<String>("project").unwrap();
+        let dry_run = matches.get_flag("dry-run");
+
+        let report = Undo::new()
+            .with_dry_run(dry_run)
+            .with_force(matches.get_flag("force"))
+            .run(project)?;
+
+        let (removed, restored) = if dry_run {
+            ("Would remove".green().bold(), "Would restore".green().bold())
+        } else {
+            ("Removed".green().bold(), "Restored".green().bold())
+        };
+        for path in report.removed_files.iter().chain(&report.removed_directories) {
+            println!("{} {}", removed, path);
+        }
+        for path in &report.restored {
+            println!("{} {}", restored, path);
+        }
+        for path in &report.missing {
+            println!("{} {} (already gone)", "Missing".yellow().bold(), path);
+        }
+        for path in &report.kept_directories {
+            println!("{} {} (not empty)", "Kept".yellow().bold(), path);
+        }
+        for path in &report.modified {
+            println!("{} {} (modified since forge; use --force to remove)", "Refused".red().bold(), path);
+        }
+
+        if report.removed_root {
+            println!("\n{} {} {}", "✅".green(), removed, project);
+        } else if report.modified.is_empty() {
+            println!("\n{} Undo complete", "✅".green());
+        } else {
+            println!("\n{} {} modified files were left in place", "⚠️".yellow(), report.modified.len());
+        }
+
+        Ok(())
+    }
+
+    /// Print the declared variable schema, if the structure has one
+    fn print_declarations(structure: &ProjectStructure) {
+        if structure.declarations.is_empty() {
+            return;
+        }
+
+        println!("  {} Variables:", "🔧".blue());
+        for (name, decl) in &structure.declarations {
+            let mut details = vec![decl.var_type.to_string()];
+            if decl.var_type == VarType::Enum {
+                details.push(decl.values.join("|"));
+            }
+            if decl.required {
+                details.push("required".to_string());
+            }
+            if let Some(default) = decl.default_string() {
+                details.push(format!("default: {}", default));
+            }
+            if let Some(pattern) = &decl.pattern {
+                details.push(format!("pattern: {}", pattern));
+            }
+
+            match &decl.description {
+                Some(description) => println!("    {} ({}) - {}", name.cyan(), details.join(", "), description),
+                None => println!("    {} ({})", name.cyan(), details.join(", ")),
+            }
+        }
+    }
+
+    fn print_hooks(structure: &ProjectStructure) {
+        if structure.hooks.is_empty() {
+            return;
+        }
+
+        println!("  {} Hooks:", "🪝".blue());
+        for stage in [HookStage::PreGenerate, HookStage::PostGenerate] {
+            for hook in structure.hooks.stage(stage) {
+                match hook.on_failure {
+                    FailurePolicy::Abort => println!("    {} {}", stage.to_string().cyan(), hook),
+                    policy => println!("    {} {} (on failure: {})", stage.to_string().cyan(), hook, policy),
+                }
+            }
+        }
+    }
+
+    /// Whether `--format json` was requested
+    fn json_output(matches: &ArgMatches) -> bool {
+        matches.get_one::<String>("format").is_some_and(|format| format == "json")
+    }
+
+    /// Read the `--on-duplicate` policy from the command line
+    fn duplicate_policy(matches: &ArgMatches) -> Result<DuplicatePolicy> {
+        matches.get_one::<String>("on-duplicate").unwrap().parse()
+    }
+
     /// Parse --var key=value pairs from command line into a HashMap
     /// Handles multiple --var flags and provides error messages for invalid formats
     fn parse_variables(matches: &ArgMatches) -> HashMap<String, String> {
@@ -155,14 +830,4 @@ impl Cli {
 
         variables
     }
-
-    /// Recursively count total number of items in the structure tree
-    /// Used for progress bar initialization and statistics display
-    fn count_total_items(items: &[crate::parser::StructureItem]) -> usize {
-        let mut count = items.len();
-        for item in items {
-            count += Self::count_total_items(&item.children);
-        }
-        count
-    }
 }
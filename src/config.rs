@@ -0,0 +1,69 @@
+//! User configuration for forge-tree
+//!
+//! Configuration is read from a YAML file, by default
+//! `~/.config/forge-tree/config.yaml` (or the path in `FORGE_TREE_CONFIG`).
+//! A missing file is not an error; it simply yields the default configuration.
+//!
+//! ```yaml
+//! variables:
+//!   author: Jane Doe
+//!   license: MIT
+//! trusted:
+//!   - ~/templates
+//! ```
+
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Config {
+    /// Default values offered for template variables
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+    /// Directories whose structure files may run hooks without asking
+    #[serde(default)]
+    pub trusted: Vec<PathBuf>,
+}
+
+impl Config {
+    /// Load the configuration from the default location
+    pub fn load() -> Result<Self> {
+        match Self::default_path() {
+            Some(path) => Self::load_from(path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Load the configuration from a specific file, falling back to defaults if it doesn't exist
+    pub fn load_from<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        let mut config: Self = serde_yaml::from_str(&content)?;
+        config.trusted = config.trusted.into_iter().map(expand_home).collect();
+        Ok(config)
+    }
+
+    /// Location of the configuration file, if one can be determined
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("FORGE_TREE_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+
+        std::env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".config").join("forge-tree").join("config.yaml"))
+    }
+}
+
+/// Expand a leading `~/` to the home directory
+fn expand_home(path: PathBuf) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path,
+    }
+}
@@ -0,0 +1,212 @@
+//! Comparing a structure against a directory on disk
+
+use crate::generator::RenderedProject;
+use crate::lockfile::{native_path, slash_path};
+use crate::parser::{ItemType, ProjectStructure};
+use crate::scanner::IgnoreSet;
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Kind of entry on either side of a comparison
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    Directory,
+    File,
+    Symlink,
+}
+
+impl std::fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Directory => write!(f, "directory"),
+            Self::File => write!(f, "file"),
+            Self::Symlink => write!(f, "symlink"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DifferenceKind {
+    /// In the structure but not on disk
+    Missing,
+    /// On disk but not in the structure
+    Extra,
+    /// A file where a directory is expected, or the other way round
+    TypeMismatch,
+    /// A file whose contents differ from what the structure renders
+    ContentMismatch,
+    /// A file or directory whose permission bits differ from the mode the structure sets
+    ModeMismatch,
+    /// A symlink pointing somewhere other than the structure's target
+    TargetMismatch,
+}
+
+/// One way the directory differs from the structure
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Difference {
+    /// Path relative to the project root, with `/` separators
+    pub path: String,
+    pub kind: DifferenceKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<EntryKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual: Option<EntryKind>,
+    /// Octal permission bits the structure sets, for mode mismatches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_mode: Option<String>,
+    /// Octal permission bits found on disk, for mode mismatches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual_mode: Option<String>,
+    /// Target the structure gives a symlink, for target mismatches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_target: Option<String>,
+    /// Target of the symlink on disk, for target mismatches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual_target: Option<String>,
+}
+
+/// Compare a rendered structure with a scanned directory
+///
+/// Only the topmost missing or extra entry of a subtree is reported. With
+/// `compare_content`, files the structure gives content to (via `content` or
+/// a template) are also compared byte-for-byte against `dir`. Files and
+/// directories the structure gives a mode are checked against the scanned
+/// mode, where the platform has one, and symlinks against their target.
+/// Differences are sorted by path.
+pub fn diff(
+    expected: &RenderedProject,
+    actual: &ProjectStructure,
+    dir: &Path,
+    ignore: &IgnoreSet,
+    compare_content: bool,
+) -> Result<Vec<Difference>> {
+    let mut wanted: BTreeMap<String, (EntryKind, Option<&[u8]>)> = BTreeMap::new();
+    let mut modes: BTreeMap<String, u32> = BTreeMap::new();
+    for directory in &expected.directories {
+        wanted.insert(slash_path(directory), (EntryKind::Directory, None));
+    }
+    for (directory, mode) in &expected.directory_modes {
+        modes.insert(slash_path(directory), *mode);
+    }
+    for file in &expected.files {
+        let contents = (!expected.without_content.contains(&file.path)).then_some(file.contents.as_slice());
+        wanted.insert(slash_path(&file.path), (EntryKind::File, contents));
+        if let Some(mode) = file.mode {
+            modes.insert(slash_path(&file.path), mode);
+        }
+    }
+    let mut targets: BTreeMap<String, String> = BTreeMap::new();
+    for link in &expected.symlinks {
+        wanted.insert(slash_path(&link.path), (EntryKind::Symlink, None));
+        targets.insert(slash_path(&link.path), link.target.to_string_lossy().into_owned());
+    }
+    wanted.retain(|path, _| !ignore.is_ignored(path));
+
+    let mut found_targets: BTreeMap<&str, &str> = BTreeMap::new();
+    let found: BTreeMap<String, (EntryKind, Option<u32>)> = actual.iter()
+        .map(|item| {
+            let kind = match &item.item_type {
+                ItemType::Directory => EntryKind::Directory,
+                ItemType::File => EntryKind::File,
+                ItemType::Symlink { target } => {
+                    found_targets.insert(&item.path, target);
+                    EntryKind::Symlink
+                }
+            };
+            (item.path.clone(), (kind, item.mode))
+        })
+        .collect();
+
+    let mut differences: Vec<Difference> = Vec::new();
+    let mut reported: Vec<String> = Vec::new();
+    let below_reported = |reported: &[String], path: &str| {
+        reported.iter().any(|parent| path.strip_prefix(parent.as_str()).is_some_and(|rest| rest.starts_with('/')))
+    };
+
+    let mut paths: Vec<&String> = wanted.keys().chain(found.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    for path in paths {
+        if below_reported(&reported, path) {
+            continue;
+        }
+
+        let expected = wanted.get(path);
+        let (actual, actual_mode) = match found.get(path) {
+            Some((kind, mode)) => (Some(*kind), *mode),
+            None => (None, None),
+        };
+
+        // Permissions are compared on their own, so a file can be both changed and have the wrong mode
+        let expected_mode = modes.get(path).copied();
+        if let (Some((expected_kind, _)), Some(expected_mode), Some(actual_mode)) = (expected, expected_mode, actual_mode) {
+            if actual == Some(*expected_kind) && expected_mode != actual_mode {
+                differences.push(Difference {
+                    path: path.clone(),
+                    kind: DifferenceKind::ModeMismatch,
+                    expected: Some(*expected_kind),
+                    actual,
+                    expected_mode: Some(format!("{:o}", expected_mode)),
+                    actual_mode: Some(format!("{:o}", actual_mode)),
+                    expected_target: None,
+                    actual_target: None,
+                });
+            }
+        }
+
+        let kind = match (expected, actual) {
+            (Some(_), None) => DifferenceKind::Missing,
+            (None, Some(_)) => DifferenceKind::Extra,
+            (Some((expected, _)), Some(actual)) if *expected != actual => DifferenceKind::TypeMismatch,
+            (Some((EntryKind::Symlink, _)), Some(_)) => {
+                let (Some(expected_target), Some(actual_target)) = (targets.get(path), found_targets.get(path.as_str()))
+                else {
+                    continue;
+                };
+                if expected_target == actual_target {
+                    continue;
+                }
+                differences.push(Difference {
+                    path: path.clone(),
+                    kind: DifferenceKind::TargetMismatch,
+                    expected: Some(EntryKind::Symlink),
+                    actual,
+                    expected_mode: None,
+                    actual_mode: None,
+                    expected_target: Some(expected_target.clone()),
+                    actual_target: Some(actual_target.to_string()),
+                });
+                continue;
+            }
+            (Some((EntryKind::File, Some(contents))), Some(_)) if compare_content => {
+                if fs::read(dir.join(native_path(path)))?.as_slice() == *contents {
+                    continue;
+                }
+                DifferenceKind::ContentMismatch
+            }
+            _ => continue,
+        };
+
+        if kind != DifferenceKind::ContentMismatch {
+            reported.push(path.clone());
+        }
+        differences.push(Difference {
+            path: path.clone(),
+            kind,
+            expected: expected.map(|(kind, _)| *kind),
+            actual,
+            expected_mode: None,
+            actual_mode: None,
+            expected_target: None,
+            actual_target: None,
+        });
+    }
+
+    Ok(differences)
+}
@@ -1,3 +1,4 @@
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, ForgeTreeError>;
@@ -7,14 +8,20 @@ pub enum ForgeTreeError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
-    #[error("Parse error: {0}")]
-    Parse(String),
+    #[error("Parse error: {message}")]
+    Parse {
+        message: String,
+        /// Structure file the error is in, when parsing a file
+        file: Option<PathBuf>,
+        /// 1-based line in `file` (or in the parsed text)
+        line: Option<usize>,
+    },
 
     #[error("Template render error: {0}")]
     TemplateRender(#[from] handlebars::RenderError),
 
     #[error("Template parse error: {0}")]
-    TemplateParse(#[from] handlebars::TemplateError),
+    TemplateParse(#[from] Box<handlebars::TemplateError>),
 
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_yaml::Error),
@@ -27,4 +34,169 @@ pub enum ForgeTreeError {
 
     #[error("Template not found: {0}")]
     TemplateNotFound(String),
+
+    #[error("Duplicate entry: {0}")]
+    DuplicateEntry(String),
+
+    #[error("Missing template variables: {0}")]
+    MissingVariables(String),
+
+    #[error("Invalid variable: {0}")]
+    InvalidVariable(String),
+
+    /// Located at the `@include` directive in the outermost file
+    #[error("Include error: {message}")]
+    Include {
+        message: String,
+        file: Option<PathBuf>,
+        line: Option<usize>,
+    },
+
+    #[error("Structure mismatch: {0}")]
+    Mismatch(String),
+
+    #[error("Hook error: {0}")]
+    Hook(String),
+
+    #[error("Git error: {0}")]
+    Git(String),
+}
+
+impl ForgeTreeError {
+    /// Process exit code for this class of failure
+    ///
+    /// | Code | Meaning |
+    /// |------|---------|
+    /// | 1 | I/O or other unexpected failure |
+    /// | 2 | Invalid command line (reported by clap) |
+    /// | 3 | Invalid structure: syntax, includes, duplicates, front matter |
+    /// | 4 | Missing or invalid variables |
+    /// | 5 | Template errors |
+    /// | 6 | Output conflicts: existing files, invalid paths |
+    /// | 7 | Directory doesn't match the structure (`check`) |
+    /// | 8 | A hook failed or wasn't allowed to run |
+    /// | 9 | Initialising or committing the git repository failed |
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Io(_) => 1,
+            Self::Parse { .. } | Self::Include { .. } | Self::DuplicateEntry(_) | Self::Serialization(_) => 3,
+            Self::MissingVariables(_) | Self::InvalidVariable(_) => 4,
+            Self::TemplateRender(_) | Self::TemplateParse(_) | Self::TemplateNotFound(_) => 5,
+            Self::FileExists(_) | Self::InvalidPath(_) => 6,
+            Self::Mismatch(_) => 7,
+            Self::Hook(_) => 8,
+            Self::Git(_) => 9,
+        }
+    }
+
+    /// Stable identifier for the variant, used in machine-readable output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io(_) => "io",
+            Self::Parse { .. } => "parse",
+            Self::TemplateRender(_) => "template_render",
+            Self::TemplateParse(_) => "template_parse",
+            Self::Serialization(_) => "serialization",
+            Self::InvalidPath(_) => "invalid_path",
+            Self::FileExists(_) => "file_exists",
+            Self::TemplateNotFound(_) => "template_not_found",
+            Self::DuplicateEntry(_) => "duplicate_entry",
+            Self::MissingVariables(_) => "missing_variables",
+            Self::InvalidVariable(_) => "invalid_variable",
+            Self::Include { .. } => "include",
+            Self::Mismatch(_) => "mismatch",
+            Self::Hook(_) => "hook",
+            Self::Git(_) => "git",
+        }
+    }
+
+    /// A parse error with no particular location
+    pub fn parse(message: impl Into<String>) -> Self {
+        Self::Parse { message: message.into(), file: None, line: None }
+    }
+
+    /// A parse error at a line of the structure being parsed
+    pub fn parse_at(line: usize, message: impl Into<String>) -> Self {
+        Self::Parse { message: message.into(), file: None, line: Some(line) }
+    }
+
+    /// Line of the structure file the error points at, if known
+    ///
+    /// For include errors this is the `@include` line in the outermost file.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Self::Parse { line, .. } | Self::Include { line, .. } => *line,
+            _ => None,
+        }
+    }
+
+    /// Structure file the error points at, if known
+    pub fn file(&self) -> Option<&Path> {
+        match self {
+            Self::Parse { file, .. } | Self::Include { file, .. } => file.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Attach a line to a parse error that has none yet
+    pub(crate) fn at_line(self, line: usize) -> Self {
+        match self {
+            Self::Parse { message, file, line: None } => Self::Parse { message, file, line: Some(line) },
+            other => other,
+        }
+    }
+
+    /// Attach the file being parsed to a parse error that has none yet
+    pub(crate) fn in_file(self, path: &Path) -> Self {
+        match self {
+            Self::Parse { message, file: None, line } => Self::Parse { message, file: Some(path.to_path_buf()), line },
+            other => other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One error of every variant, with the exit code and kind it must report
+    fn every_variant() -> Vec<(ForgeTreeError, i32, &'static str)> {
+        let template_error = handlebars::Template::compile("{{#if}}").unwrap_err();
+        let yaml_error = serde_yaml::from_str::<u32>("[").unwrap_err();
+        vec![
+            (std::io::Error::other("disk full").into(), 1, "io"),
+            (ForgeTreeError::parse("bad line"), 3, "parse"),
+            (ForgeTreeError::Include { message: "cycle".into(), file: None, line: None }, 3, "include"),
+            (ForgeTreeError::DuplicateEntry("src".into()), 3, "duplicate_entry"),
+            (yaml_error.into(), 3, "serialization"),
+            (ForgeTreeError::MissingVariables("name".into()), 4, "missing_variables"),
+            (ForgeTreeError::InvalidVariable("port".into()), 4, "invalid_variable"),
+            (handlebars::RenderError::new("missing helper").into(), 5, "template_render"),
+            (Box::new(template_error).into(), 5, "template_parse"),
+            (ForgeTreeError::TemplateNotFound("rust".into()), 5, "template_not_found"),
+            (ForgeTreeError::FileExists("README.md".into()), 6, "file_exists"),
+            (ForgeTreeError::InvalidPath("../x".into()), 6, "invalid_path"),
+            (ForgeTreeError::Mismatch("2 differences".into()), 7, "mismatch"),
+            (ForgeTreeError::Hook("make failed".into()), 8, "hook"),
+            (ForgeTreeError::Git("no git".into()), 9, "git"),
+        ]
+    }
+
+    #[test]
+    fn every_variant_has_its_exit_code_and_kind() {
+        for (error, code, kind) in every_variant() {
+            assert_eq!((error.exit_code(), error.kind()), (code, kind), "{}", error);
+        }
+    }
+
+    #[test]
+    fn parse_errors_carry_their_location() {
+        let error = ForgeTreeError::parse("bad").at_line(4).in_file(Path::new("app.tree"));
+        assert_eq!((error.line(), error.file()), (Some(4), Some(Path::new("app.tree"))));
+
+        // A location that is already known is kept
+        let error = ForgeTreeError::parse_at(2, "bad").at_line(9);
+        assert_eq!(error.line(), Some(2));
+        assert_eq!(ForgeTreeError::Hook("x".into()).at_line(3).line(), None);
+    }
 }
@@ -0,0 +1,310 @@
+//! Writing generated projects into archives
+
+use crate::generator::backend::{MemoryBackend, MemoryEntry, OutputBackend};
+use crate::{ForgeTreeError, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, DateTime, ZipWriter};
+
+/// Default permission bits for entries without an explicit mode
+const DIR_MODE: u32 = 0o755;
+const FILE_MODE: u32 = 0o644;
+const SYMLINK_MODE: u32 = 0o777;
+
+/// Supported archive formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Pick the format from a file name (`.tar`, `.tar.gz`/`.tgz` or `.zip`)
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let name = path.as_ref().file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Ok(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Ok(Self::Tar)
+        } else if name.ends_with(".zip") {
+            Ok(Self::Zip)
+        } else {
+            Err(ForgeTreeError::InvalidPath(format!(
+                "Unknown archive format: {} (expected .tar, .tar.gz, .tgz or .zip)", path.as_ref().display()
+            )))
+        }
+    }
+}
+
+/// Collects the generated tree and writes it as an archive on `finish`
+///
+/// Entries are buffered in memory so they can be written sorted by path,
+/// with fixed timestamps and no owner information, which makes archives of
+/// the same project identical byte-for-byte.
+pub struct ArchiveBackend<W: Write + Send> {
+    format: ArchiveFormat,
+    /// Archive file name shown in messages, when writing to a file
+    label: Option<PathBuf>,
+    entries: MemoryBackend,
+    writer: Mutex<Option<W>>,
+    /// File being written until `finish` renames it to `label`; removed if that never happens
+    pending: Mutex<Option<PathBuf>>,
+}
+
+impl ArchiveBackend<File> {
+    /// Archive into a file, choosing the format from its name
+    ///
+    /// The archive is written to a temporary file next to `path` and only
+    /// replaces `path` once `finish` succeeds, so a failed run leaves any
+    /// existing file untouched.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let format = ArchiveFormat::from_path(path)?;
+        let (file, pending) = create_pending(path)?;
+        let mut backend = Self::new(file, format);
+        backend.label = Some(path.to_path_buf());
+        backend.pending = Mutex::new(Some(pending));
+        Ok(backend)
+    }
+}
+
+/// Create a new, uniquely named temporary file in the directory of `path`
+fn create_pending(path: &Path) -> Result<(File, PathBuf)> {
+    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    for attempt in 0..100 {
+        let pending = path.with_file_name(format!(".{}.{}-{}.tmp", name, std::process::id(), attempt));
+        match File::options().write(true).create_new(true).open(&pending) {
+            Ok(file) => return Ok((file, pending)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err(ForgeTreeError::InvalidPath(format!("Couldn't create a temporary file next to {}", path.display())))
+}
+
+impl<W: Write + Send> ArchiveBackend<W> {
+    pub fn new(writer: W, format: ArchiveFormat) -> Self {
+        Self {
+            format,
+            label: None,
+            entries: MemoryBackend::new(),
+            writer: Mutex::new(Some(writer)),
+            pending: Mutex::new(None),
+        }
+    }
+
+    fn write_archive(&self, writer: W) -> Result<W> {
+        match self.format {
+            ArchiveFormat::Tar => self.write_tar(writer),
+            ArchiveFormat::TarGz => {
+                // The gzip header carries no file name and a zero mtime
+                let encoder = GzEncoder::new(writer, Compression::default());
+                Ok(self.write_tar(encoder)?.finish()?)
+            }
+            ArchiveFormat::Zip => {
+                // Zip needs to seek back to patch headers, so build it in memory first
+                let mut writer = writer;
+                writer.write_all(&self.write_zip()?)?;
+                Ok(writer)
+            }
+        }
+    }
+
+    fn write_tar<T: Write>(&self, writer: T) -> Result<T> {
+        let mut builder = tar::Builder::new(writer);
+
+        for (path, entry) in self.entries.entries() {
+            let mut header = tar::Header::new_gnu();
+            header.set_mtime(0);
+            header.set_uid(0);
+            header.set_gid(0);
+
+            match entry {
+                MemoryEntry::Directory { mode } => {
+                    header.set_entry_type(tar::EntryType::Directory);
+                    header.set_mode(mode.unwrap_or(DIR_MODE));
+                    header.set_size(0);
+                    builder.append_data(&mut header, &path, std::io::empty())?;
+                }
+                MemoryEntry::File { contents, mode } => {
+                    header.set_entry_type(tar::EntryType::Regular);
+                    header.set_mode(mode.unwrap_or(FILE_MODE));
+                    header.set_size(contents.len() as u64);
+                    builder.append_data(&mut header, &path, contents.as_slice())?;
+                }
+                MemoryEntry::Symlink { target } => {
+                    header.set_entry_type(tar::EntryType::Symlink);
+                    header.set_mode(SYMLINK_MODE);
+                    header.set_size(0);
+                    builder.append_link(&mut header, &path, &target)?;
+                }
+            }
+        }
+
+        Ok(builder.into_inner()?)
+    }
+
+    fn write_zip(&self) -> Result<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .last_modified_time(DateTime::default());
+
+        for (path, entry) in self.entries.entries() {
+            let name = archive_name(&path);
+            match entry {
+                MemoryEntry::Directory { mode } => {
+                    zip.add_directory(name, options.unix_permissions(mode.unwrap_or(DIR_MODE)))
+                        .map_err(zip_error)?;
+                }
+                MemoryEntry::File { contents, mode } => {
+                    zip.start_file(name, options.unix_permissions(mode.unwrap_or(FILE_MODE)))
+                        .map_err(zip_error)?;
+                    zip.write_all(&contents)?;
+                }
+                MemoryEntry::Symlink { target } => {
+                    zip.add_symlink(name, archive_name(&target), options.unix_permissions(SYMLINK_MODE))
+                        .map_err(zip_error)?;
+                }
+            }
+        }
+
+        Ok(zip.finish().map_err(zip_error)?.into_inner())
+    }
+}
+
+/// Archive member name with `/` separators regardless of platform
+fn archive_name(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn zip_error(e: zip::result::ZipError) -> ForgeTreeError {
+    match e {
+        zip::result::ZipError::Io(e) => ForgeTreeError::Io(e),
+        other => ForgeTreeError::Io(std::io::Error::other(other)),
+    }
+}
+
+impl<W: Write + Send> OutputBackend for ArchiveBackend<W> {
+    fn create_dir(&self, path: &Path) -> Result<()> {
+        self.entries.create_dir(path)
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        self.entries.write_file(path, contents)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.entries.exists(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.entries.is_dir(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.entries.rename(from, to)
+    }
+
+    fn set_permissions(&self, path: &Path, mode: u32) -> Result<()> {
+        self.entries.set_permissions(path, mode)
+    }
+
+    fn symlink(&self, target: &Path, link: &Path) -> Result<()> {
+        self.entries.symlink(target, link)
+    }
+
+    fn location(&self, path: &Path) -> PathBuf {
+        match &self.label {
+            Some(label) => PathBuf::from(format!("{}:{}", label.display(), archive_name(path))),
+            None => path.to_path_buf(),
+        }
+    }
+
+    fn finish(&self) -> Result<()> {
+        let writer = self.writer.lock().unwrap().take()
+            .ok_or_else(|| ForgeTreeError::InvalidPath("Archive has already been written".to_string()))?;
+        let mut writer = self.write_archive(writer)?;
+        writer.flush()?;
+        drop(writer);
+
+        if let (Some(pending), Some(label)) = (self.pending.lock().unwrap().take(), &self.label) {
+            if let Err(e) = std::fs::rename(&pending, label) {
+                let _ = std::fs::remove_file(&pending);
+                return Err(e.into());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write + Send> Drop for ArchiveBackend<W> {
+    fn drop(&mut self) {
+        // Close the file before removing it, which Windows requires
+        drop(self.writer.get_mut().unwrap_or_else(|e| e.into_inner()).take());
+        if let Some(pending) = self.pending.get_mut().unwrap_or_else(|e| e.into_inner()).take() {
+            let _ = std::fs::remove_file(pending);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn files_in(dir: &Path) -> Vec<String> {
+        let mut names: Vec<_> = fs::read_dir(dir).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn replaces_the_target_only_once_finished() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let target = dir.path().join("app.tar");
+        fs::write(&target, "existing").unwrap();
+
+        let backend = ArchiveBackend::create(&target).unwrap();
+        backend.write_file(Path::new("app/README.md"), b"hello").unwrap();
+        assert_eq!(fs::read(&target).unwrap(), b"existing");
+
+        backend.finish().unwrap();
+        drop(backend);
+
+        assert_eq!(files_in(dir.path()), ["app.tar"]);
+        let mut archive = tar::Archive::new(fs::File::open(&target).unwrap());
+        let paths: Vec<_> = archive.entries().unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(paths, ["app", "app/README.md"]);
+    }
+
+    #[test]
+    fn unfinished_archives_leave_existing_files_alone() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let target = dir.path().join("app.zip");
+        fs::write(&target, "existing").unwrap();
+
+        let backend = ArchiveBackend::create(&target).unwrap();
+        backend.write_file(Path::new("app/README.md"), b"hello").unwrap();
+        drop(backend);
+
+        assert_eq!(files_in(dir.path()), ["app.zip"]);
+        assert_eq!(fs::read(&target).unwrap(), b"existing");
+    }
+}
@@ -0,0 +1,358 @@
+//! Output backends that generated files are written to
+//!
+//! The generator never touches `std::fs` directly; it goes through an
+//! [`OutputBackend`], so the same pipeline can write to disk, into memory
+//! or into an archive.
+
+use crate::{ForgeTreeError, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Where generated directories and files end up
+///
+/// Paths are relative to the backend's root and use the platform separator.
+/// `create_dir` behaves like `create_dir_all`, and `write_file` and `symlink`
+/// replace any existing file or link. `exists` is also true for a symlink
+/// whose target is missing.
+pub trait OutputBackend: Send + Sync {
+    fn create_dir(&self, path: &Path) -> Result<()>;
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> Result<()>;
+
+    fn exists(&self, path: &Path) -> bool;
+
+    fn is_dir(&self, path: &Path) -> bool;
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()>;
+
+    /// Set Unix permission bits (e.g. `0o755`)
+    fn set_permissions(&self, path: &Path, mode: u32) -> Result<()>;
+
+    /// Create a symlink at `link` pointing to `target`, relative to the link's directory unless absolute
+    fn symlink(&self, target: &Path, link: &Path) -> Result<()>;
+
+    /// Human-readable location of a path, used in messages
+    fn location(&self, path: &Path) -> PathBuf {
+        path.to_path_buf()
+    }
+
+    /// Directory on the local filesystem a path ends up at, if it does
+    ///
+    /// Hooks only run for backends that write real directories.
+    fn local_path(&self, _path: &Path) -> Option<PathBuf> {
+        None
+    }
+
+    /// Flush anything buffered (e.g. write out an archive)
+    fn finish(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes to the real filesystem below a root directory
+#[derive(Debug, Clone, Default)]
+pub struct FsBackend {
+    root: PathBuf,
+}
+
+impl FsBackend {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn full_path(&self, path: &Path) -> PathBuf {
+        self.root.join(path)
+    }
+}
+
+impl OutputBackend for FsBackend {
+    fn create_dir(&self, path: &Path) -> Result<()> {
+        fs::create_dir_all(self.full_path(path))?;
+        Ok(())
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        fs::write(self.full_path(path), contents)?;
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        fs::symlink_metadata(self.full_path(path)).is_ok()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.full_path(path).is_dir()
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        fs::rename(self.full_path(from), self.full_path(to))?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn set_permissions(&self, path: &Path, mode: u32) -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(self.full_path(path), fs::Permissions::from_mode(mode))?;
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn set_permissions(&self, _path: &Path, _mode: u32) -> Result<()> {
+        // Only Unix has permission bits; elsewhere modes are ignored
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn symlink(&self, target: &Path, link: &Path) -> Result<()> {
+        let link = self.full_path(link);
+        remove_non_directory(&link)?;
+        std::os::unix::fs::symlink(target, link)?;
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn symlink(&self, target: &Path, link: &Path) -> Result<()> {
+        let link = self.full_path(link);
+        remove_non_directory(&link)?;
+        let resolved = link.parent().map(|parent| parent.join(target)).unwrap_or_else(|| target.to_path_buf());
+        if resolved.is_dir() {
+            std::os::windows::fs::symlink_dir(target, link)?;
+        } else {
+            std::os::windows::fs::symlink_file(target, link)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn symlink(&self, _target: &Path, link: &Path) -> Result<()> {
+        Err(ForgeTreeError::InvalidPath(format!(
+            "Symlinks are not supported on this platform: {}", link.display()
+        )))
+    }
+
+    fn location(&self, path: &Path) -> PathBuf {
+        self.full_path(path)
+    }
+
+    fn local_path(&self, path: &Path) -> Option<PathBuf> {
+        Some(self.full_path(path))
+    }
+}
+
+/// Clear the way for a new link: remove a file or link at `path`, refuse a real directory
+#[cfg(any(unix, windows))]
+fn remove_non_directory(path: &Path) -> Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => Err(ForgeTreeError::InvalidPath(format!(
+            "Path exists but is not a file: {}", path.display()
+        ))),
+        Ok(metadata) if metadata.is_symlink() && path.is_dir() && cfg!(windows) => Ok(fs::remove_dir(path)?),
+        Ok(_) => Ok(fs::remove_file(path)?),
+        Err(_) => Ok(()),
+    }
+}
+
+/// An entry held by [`MemoryBackend`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoryEntry {
+    Directory { mode: Option<u32> },
+    File { contents: Vec<u8>, mode: Option<u32> },
+    Symlink { target: PathBuf },
+}
+
+/// Keeps the generated tree in memory, for tests and previews
+///
+/// Entries are kept sorted by path, so iteration order is deterministic.
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    entries: Mutex<BTreeMap<PathBuf, MemoryEntry>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every entry, sorted by path
+    pub fn entries(&self) -> Vec<(PathBuf, MemoryEntry)> {
+        self.entries.lock().unwrap()
+            .iter()
+            .map(|(path, entry)| (path.clone(), entry.clone()))
+            .collect()
+    }
+
+    pub fn get(&self, path: &Path) -> Option<MemoryEntry> {
+        self.entries.lock().unwrap().get(path).cloned()
+    }
+
+    /// Contents of a file, if `path` is one
+    pub fn read(&self, path: &Path) -> Option<Vec<u8>> {
+        match self.get(path)? {
+            MemoryEntry::File { contents, .. } => Some(contents),
+            _ => None,
+        }
+    }
+
+    fn insert_parents(entries: &mut BTreeMap<PathBuf, MemoryEntry>, path: &Path) -> Result<()> {
+        for ancestor in path.ancestors().skip(1).filter(|a| !a.as_os_str().is_empty()) {
+            match entries.get(ancestor) {
+                None => {
+                    entries.insert(ancestor.to_path_buf(), MemoryEntry::Directory { mode: None });
+                }
+                Some(MemoryEntry::Directory { .. }) => {}
+                Some(_) => {
+                    return Err(ForgeTreeError::InvalidPath(format!(
+                        "Path exists but is not a directory: {}", ancestor.display()
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl OutputBackend for MemoryBackend {
+    fn create_dir(&self, path: &Path) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        Self::insert_parents(&mut entries, path)?;
+        match entries.get(path) {
+            None => {
+                entries.insert(path.to_path_buf(), MemoryEntry::Directory { mode: None });
+                Ok(())
+            }
+            Some(MemoryEntry::Directory { .. }) => Ok(()),
+            Some(_) => Err(ForgeTreeError::InvalidPath(format!(
+                "Path exists but is not a directory: {}", path.display()
+            ))),
+        }
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        Self::insert_parents(&mut entries, path)?;
+        let mode = match entries.get(path) {
+            Some(MemoryEntry::Directory { .. }) => {
+                return Err(ForgeTreeError::InvalidPath(format!(
+                    "Path exists but is not a file: {}", path.display()
+                )));
+            }
+            Some(MemoryEntry::File { mode, .. }) => *mode,
+            _ => None,
+        };
+        entries.insert(path.to_path_buf(), MemoryEntry::File { contents: contents.to_vec(), mode });
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.entries.lock().unwrap().contains_key(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        matches!(self.get(path), Some(MemoryEntry::Directory { .. }))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+
+        // Move the entry together with everything below it
+        let moved: Vec<PathBuf> = entries.keys().filter(|path| path.starts_with(from)).cloned().collect();
+        if moved.is_empty() {
+            return Err(ForgeTreeError::InvalidPath(format!("No such entry: {}", from.display())));
+        }
+        for path in moved {
+            let entry = entries.remove(&path).expect("key was just listed");
+            let relative = path.strip_prefix(from).expect("filtered by starts_with");
+            entries.insert(to.join(relative), entry);
+        }
+        Ok(())
+    }
+
+    fn set_permissions(&self, path: &Path, new_mode: u32) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get_mut(path) {
+            Some(MemoryEntry::Directory { mode }) | Some(MemoryEntry::File { mode, .. }) => {
+                *mode = Some(new_mode);
+                Ok(())
+            }
+            // Symlink permissions are meaningless, like on Linux
+            Some(MemoryEntry::Symlink { .. }) => Ok(()),
+            None => Err(ForgeTreeError::InvalidPath(format!("No such entry: {}", path.display()))),
+        }
+    }
+
+    fn symlink(&self, target: &Path, link: &Path) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        Self::insert_parents(&mut entries, link)?;
+        if let Some(MemoryEntry::Directory { .. }) = entries.get(link) {
+            return Err(ForgeTreeError::InvalidPath(format!(
+                "Path exists but is not a file: {}", link.display()
+            )));
+        }
+        entries.insert(link.to_path_buf(), MemoryEntry::Symlink { target: target.to_path_buf() });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The behaviour every backend shares, as described on [`OutputBackend`]
+    fn check_backend(backend: &dyn OutputBackend) {
+        backend.create_dir(Path::new("app/src")).unwrap();
+        backend.create_dir(Path::new("app/src")).unwrap();
+        assert!(backend.is_dir(Path::new("app")) && backend.is_dir(Path::new("app/src")));
+
+        backend.write_file(Path::new("app/src/main.rs"), b"old").unwrap();
+        backend.write_file(Path::new("app/src/main.rs"), b"new").unwrap();
+        assert!(backend.exists(Path::new("app/src/main.rs")));
+        assert!(!backend.is_dir(Path::new("app/src/main.rs")));
+        assert!(backend.write_file(Path::new("app/src"), b"").is_err());
+
+        backend.rename(Path::new("app/src/main.rs"), Path::new("app/src/lib.rs")).unwrap();
+        assert!(!backend.exists(Path::new("app/src/main.rs")));
+        assert!(backend.exists(Path::new("app/src/lib.rs")));
+
+        backend.symlink(Path::new("missing.rs"), Path::new("app/link.rs")).unwrap();
+        assert!(backend.exists(Path::new("app/link.rs")));
+        assert!(backend.symlink(Path::new("lib.rs"), Path::new("app/src")).is_err());
+
+        backend.set_permissions(Path::new("app/src/lib.rs"), 0o600).unwrap();
+        backend.finish().unwrap();
+    }
+
+    #[test]
+    fn memory_backend_keeps_entries_sorted_with_their_modes() {
+        let backend = MemoryBackend::new();
+        check_backend(&backend);
+
+        let paths: Vec<_> = backend.entries().into_iter().map(|(path, _)| path).collect();
+        assert_eq!(paths, ["app", "app/link.rs", "app/src", "app/src/lib.rs"].map(PathBuf::from));
+        assert_eq!(
+            backend.get(Path::new("app/src/lib.rs")),
+            Some(MemoryEntry::File { contents: b"new".to_vec(), mode: Some(0o600) })
+        );
+        assert_eq!(backend.location(Path::new("app")), PathBuf::from("app"));
+        assert_eq!(backend.local_path(Path::new("app")), None);
+        assert!(backend.set_permissions(Path::new("nothing"), 0o600).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fs_backend_writes_below_its_root() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let backend = FsBackend::new(dir.path());
+        check_backend(&backend);
+
+        let lib = dir.path().join("app/src/lib.rs");
+        assert_eq!(fs::read(&lib).unwrap(), b"new");
+        assert_eq!(fs::metadata(&lib).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(fs::read_link(dir.path().join("app/link.rs")).unwrap(), PathBuf::from("missing.rs"));
+        assert_eq!(backend.location(Path::new("app")), dir.path().join("app"));
+        assert_eq!(backend.local_path(Path::new("app")), Some(dir.path().join("app")));
+    }
+}
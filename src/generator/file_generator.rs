@@ -1,26 +1,82 @@
 //! File and directory creation utilities
 //! 
-//! This module handles the actual filesystem operations for creating
-//! directories and files, with support for force overwrite mode.
+//! This module handles creating directories and files through an
+//! output backend, with configurable handling of existing files.
 
+use crate::generator::backend::{FsBackend, OutputBackend};
+use crate::generator::prompt;
 use crate::{Result, ForgeTreeError};
-use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
-/// Handles creation of files and directories on the filesystem
+/// What to do when a file about to be created already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// Fail with a `FileExists` error
+    #[default]
+    Error,
+    /// Leave the existing file untouched
+    Skip,
+    /// Replace the existing file
+    Overwrite,
+    /// Rename the existing file to `file.orig` (or `file.~N~`) before writing
+    Backup,
+    /// Ask on the terminal for each file
+    Prompt,
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = ForgeTreeError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "error" => Ok(Self::Error),
+            "skip" => Ok(Self::Skip),
+            "overwrite" => Ok(Self::Overwrite),
+            "backup" => Ok(Self::Backup),
+            "prompt" => Ok(Self::Prompt),
+            other => Err(ForgeTreeError::parse(format!(
+                "Unknown conflict policy: {} (expected error, skip, overwrite, backup or prompt)", other
+            ))),
+        }
+    }
+}
+
+/// Result of a single `create_file` call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileOutcome {
+    /// The file did not exist and was written
+    Created,
+    /// The file existed and was left untouched
+    Skipped,
+    /// The file existed and was replaced
+    Overwritten,
+    /// The file existed, was moved to the given backup path, and was replaced
+    BackedUp(PathBuf),
+}
+
+/// Handles creation of files and directories
 /// 
 /// FileGenerator provides safe file operations with configurable
-/// overwrite behavior and proper error handling.
+/// conflict behavior and proper error handling. It writes to the real
+/// filesystem unless given another backend.
 pub struct FileGenerator {
-    /// Whether to overwrite existing files without error
-    force_overwrite: bool,
+    /// Where directories and files are written
+    backend: Arc<dyn OutputBackend>,
+    /// How to handle files that already exist
+    conflict_policy: ConflictPolicy,
+    /// Sticky "all"/"none" answer given during prompting
+    prompt_answer: Mutex<Option<bool>>,
 }
 
 impl FileGenerator {
-    /// Create a new FileGenerator with default settings (no force overwrite)
+    /// Create a new FileGenerator with default settings (error on existing files)
     pub fn new() -> Self {
         Self {
-            force_overwrite: false,
+            backend: Arc::new(FsBackend::default()),
+            conflict_policy: ConflictPolicy::default(),
+            prompt_answer: Mutex::new(None),
         }
     }
 
@@ -28,8 +84,20 @@ impl FileGenerator {
     /// 
     /// When force_overwrite is true, existing files will be replaced.
     /// When false, attempting to create existing files will return an error.
-    pub fn with_force_overwrite(mut self, force: bool) -> Self {
-        self.force_overwrite = force;
+    pub fn with_force_overwrite(self, force: bool) -> Self {
+        let policy = if force { ConflictPolicy::Overwrite } else { ConflictPolicy::Error };
+        self.with_conflict_policy(policy)
+    }
+
+    /// Configure how existing files are handled
+    pub fn with_conflict_policy(mut self, policy: ConflictPolicy) -> Self {
+        self.conflict_policy = policy;
+        self
+    }
+
+    /// Write through the given backend instead of the real filesystem
+    pub fn with_backend(mut self, backend: Arc<dyn OutputBackend>) -> Self {
+        self.backend = backend;
         self
     }
 
@@ -43,39 +111,139 @@ impl FileGenerator {
         let path = path.as_ref();
         
         // Check if path exists and is not a directory (e.g., it's a file)
-        if path.exists() && !path.is_dir() {
+        if self.backend.exists(path) && !self.backend.is_dir(path) {
             return Err(ForgeTreeError::InvalidPath(
-                format!("Path exists but is not a directory: {}", path.display())
+                format!("Path exists but is not a directory: {}", self.backend.location(path).display())
             ));
         }
 
-        // create_dir_all is idempotent - it won't error if directory already exists
-        fs::create_dir_all(path)?;
-        Ok(())
+        // Backends create missing parents and accept existing directories
+        self.backend.create_dir(path)
     }
 
     /// Create a file with the specified content
     /// 
     /// This method:
-    /// - Checks for existing files and respects the force_overwrite setting
+    /// - Resolves conflicts with existing files according to the conflict policy
     /// - Creates parent directories if they don't exist
-    /// - Writes the content to the file (overwrites if file exists and force is true)
-    pub fn create_file<P: AsRef<Path>>(&self, path: P, content: &str) -> Result<()> {
+    /// - Writes the content to the file and reports what happened
+    pub fn create_file<P: AsRef<Path>>(&self, path: P, content: &str) -> Result<FileOutcome> {
         let path = path.as_ref();
 
-        // Check if file already exists and we're not in force mode
-        if path.exists() && !self.force_overwrite {
-            return Err(ForgeTreeError::FileExists(path.display().to_string()));
+        if self.backend.is_dir(path) {
+            return Err(ForgeTreeError::InvalidPath(
+                format!("Path exists but is not a file: {}", self.backend.location(path).display())
+            ));
+        }
+
+        let outcome = self.resolve_conflict(path)?;
+        if outcome == FileOutcome::Skipped {
+            return Ok(outcome);
         }
 
         // Ensure the parent directory exists before creating the file
-        if let Some(parent) = path.parent() {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
             self.create_directory(parent)?;
         }
 
-        // fs::write automatically creates or overwrites the file
-        fs::write(path, content)?;
-        Ok(())
+        // Backends create or overwrite the file
+        self.backend.write_file(path, content.as_bytes())?;
+        Ok(outcome)
+    }
+
+    /// Create a symlink at `path` pointing to `target`
+    ///
+    /// Anything already at `path` (including another symlink) is handled by
+    /// the conflict policy, like an existing file.
+    pub fn create_symlink<P: AsRef<Path>>(&self, path: P, target: &Path) -> Result<FileOutcome> {
+        let path = path.as_ref();
+
+        let outcome = self.resolve_conflict(path)?;
+        if outcome == FileOutcome::Skipped {
+            return Ok(outcome);
+        }
+
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            self.create_directory(parent)?;
+        }
+
+        // Backends replace an existing file or link
+        self.backend.symlink(target, path)?;
+        Ok(outcome)
+    }
+
+    /// Decide what happens to an entry about to be written, backing up what's there if needed
+    fn resolve_conflict(&self, path: &Path) -> Result<FileOutcome> {
+        let outcome = if self.backend.exists(path) {
+            match self.conflict_policy {
+                ConflictPolicy::Error => {
+                    return Err(ForgeTreeError::FileExists(self.backend.location(path).display().to_string()));
+                }
+                ConflictPolicy::Skip => FileOutcome::Skipped,
+                ConflictPolicy::Overwrite => FileOutcome::Overwritten,
+                ConflictPolicy::Backup => FileOutcome::BackedUp(self.backup(path)?),
+                ConflictPolicy::Prompt => {
+                    if self.confirm_overwrite(path)? {
+                        FileOutcome::Overwritten
+                    } else {
+                        FileOutcome::Skipped
+                    }
+                }
+            }
+        } else {
+            FileOutcome::Created
+        };
+        Ok(outcome)
+    }
+
+    /// Move an existing file out of the way, returning its new location
+    ///
+    /// The first backup is `file.orig`; later ones are numbered `file.~1~`,
+    /// `file.~2~`, ... so earlier backups are never clobbered.
+    fn backup(&self, path: &Path) -> Result<PathBuf> {
+        let file_name = path.file_name()
+            .ok_or_else(|| ForgeTreeError::InvalidPath(path.display().to_string()))?
+            .to_string_lossy()
+            .into_owned();
+
+        let mut backup_path = path.with_file_name(format!("{}.orig", file_name));
+        let mut counter = 1;
+        while self.backend.exists(&backup_path) {
+            backup_path = path.with_file_name(format!("{}.~{}~", file_name, counter));
+            counter += 1;
+        }
+
+        self.backend.rename(path, &backup_path)?;
+        Ok(backup_path)
+    }
+
+    /// Ask whether an existing file should be overwritten
+    ///
+    /// Answering "all" or "none" applies to every remaining conflict.
+    fn confirm_overwrite(&self, path: &Path) -> Result<bool> {
+        let mut sticky = self.prompt_answer.lock().unwrap();
+        if let Some(answer) = *sticky {
+            return Ok(answer);
+        }
+
+        loop {
+            let question = format!(
+                "{} already exists. Overwrite? [y]es/[n]o/[a]ll/[N]one:", self.backend.location(path).display()
+            );
+            match prompt::ask(&question)?.as_str() {
+                "y" | "yes" => return Ok(true),
+                "n" | "no" | "" => return Ok(false),
+                "a" | "all" => {
+                    *sticky = Some(true);
+                    return Ok(true);
+                }
+                "N" | "none" => {
+                    *sticky = Some(false);
+                    return Ok(false);
+                }
+                _ => continue,
+            }
+        }
     }
 }
 
@@ -84,3 +252,95 @@ impl Default for FileGenerator {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::backend::MemoryBackend;
+
+    /// A generator writing into memory, with `existing.txt` already there
+    fn generator(policy: ConflictPolicy) -> (FileGenerator, Arc<MemoryBackend>) {
+        let backend = Arc::new(MemoryBackend::new());
+        backend.write_file(Path::new("existing.txt"), b"old").unwrap();
+        let generator = FileGenerator::new().with_backend(backend.clone()).with_conflict_policy(policy);
+        (generator, backend)
+    }
+
+    #[test]
+    fn parses_policy_names() {
+        assert_eq!("backup".parse::<ConflictPolicy>().unwrap(), ConflictPolicy::Backup);
+        assert_eq!("prompt".parse::<ConflictPolicy>().unwrap(), ConflictPolicy::Prompt);
+        assert!("replace".parse::<ConflictPolicy>().is_err());
+    }
+
+    #[test]
+    fn new_files_are_created_under_any_policy() {
+        let (generator, backend) = generator(ConflictPolicy::Error);
+
+        assert_eq!(generator.create_file("src/new.txt", "new").unwrap(), FileOutcome::Created);
+        assert_eq!(backend.read(Path::new("src/new.txt")).unwrap(), b"new");
+    }
+
+    #[test]
+    fn error_policy_fails_on_existing_files() {
+        let (generator, backend) = generator(ConflictPolicy::Error);
+
+        let error = generator.create_file("existing.txt", "new").unwrap_err();
+
+        assert!(matches!(error, ForgeTreeError::FileExists(_)), "{}", error);
+        assert_eq!(backend.read(Path::new("existing.txt")).unwrap(), b"old");
+    }
+
+    #[test]
+    fn skip_policy_leaves_existing_files_alone() {
+        let (generator, backend) = generator(ConflictPolicy::Skip);
+
+        assert_eq!(generator.create_file("existing.txt", "new").unwrap(), FileOutcome::Skipped);
+        assert_eq!(backend.read(Path::new("existing.txt")).unwrap(), b"old");
+    }
+
+    #[test]
+    fn overwrite_policy_replaces_existing_files() {
+        let (generator, backend) = generator(ConflictPolicy::Overwrite);
+
+        assert_eq!(generator.create_file("existing.txt", "new").unwrap(), FileOutcome::Overwritten);
+        assert_eq!(backend.read(Path::new("existing.txt")).unwrap(), b"new");
+    }
+
+    #[test]
+    fn backup_policy_never_clobbers_earlier_backups() {
+        let (generator, backend) = generator(ConflictPolicy::Backup);
+
+        let first = generator.create_file("existing.txt", "second").unwrap();
+        let second = generator.create_file("existing.txt", "third").unwrap();
+
+        assert_eq!(first, FileOutcome::BackedUp(PathBuf::from("existing.txt.orig")));
+        assert_eq!(second, FileOutcome::BackedUp(PathBuf::from("existing.txt.~1~")));
+        assert_eq!(backend.read(Path::new("existing.txt.orig")).unwrap(), b"old");
+        assert_eq!(backend.read(Path::new("existing.txt.~1~")).unwrap(), b"second");
+        assert_eq!(backend.read(Path::new("existing.txt")).unwrap(), b"third");
+    }
+
+    #[test]
+    fn prompt_policy_applies_an_all_or_none_answer_to_later_files() {
+        let (generator, backend) = generator(ConflictPolicy::Prompt);
+        backend.write_file(Path::new("other.txt"), b"old").unwrap();
+
+        *generator.prompt_answer.lock().unwrap() = Some(false);
+        assert_eq!(generator.create_file("existing.txt", "new").unwrap(), FileOutcome::Skipped);
+
+        *generator.prompt_answer.lock().unwrap() = Some(true);
+        assert_eq!(generator.create_file("other.txt", "new").unwrap(), FileOutcome::Overwritten);
+        assert_eq!(backend.read(Path::new("existing.txt")).unwrap(), b"old");
+        assert_eq!(backend.read(Path::new("other.txt")).unwrap(), b"new");
+    }
+
+    #[test]
+    fn files_never_replace_directories() {
+        let (generator, _) = generator(ConflictPolicy::Overwrite);
+        generator.create_directory("src").unwrap();
+
+        assert!(generator.create_file("src", "new").is_err());
+        assert!(generator.create_directory("existing.txt").is_err());
+    }
+}
@@ -1,130 +1,762 @@
 //! Project generation module
 
+pub mod archive;
+pub mod backend;
 pub mod file_generator;
+pub mod plan;
+pub mod pool;
+pub mod progress;
+pub mod prompt;
+pub mod render;
 pub mod template_engine;
 
-pub use file_generator::FileGenerator;
+pub use archive::{ArchiveBackend, ArchiveFormat};
+pub use backend::{FsBackend, MemoryBackend, MemoryEntry, OutputBackend};
+pub use file_generator::{ConflictPolicy, FileGenerator, FileOutcome};
+pub use plan::{GenerationPlan, PlannedEntry};
+pub use progress::{JsonLinesReporter, PlainReporter, ProgressReporter, SilentReporter, SkipReason, TerminalReporter};
+pub use render::{RenderedFile, RenderedProject, RenderedSymlink};
 pub use template_engine::TemplateEngine;
 
-use crate::parser::ProjectStructure;
-use crate::Result;
+use crate::git::{self, GitOptions, GitRunner, GITIGNORE_NAME};
+use crate::hooks::{HookRunner, HookStage};
+use crate::lockfile::{LockSource, Lockfile, LOCKFILE_NAME};
+use crate::parser::{ItemType, ProjectStructure, StructureItem};
+use plan::{PendingRender, Planner, LOOP_ITEM};
+use crate::variables::{self, VarType, VariableDecl};
+use crate::{ForgeTreeError, Result};
 use colored::*;
-use indicatif::{ProgressBar, ProgressStyle};
-use std::path::Path;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Main generator struct that coordinates project creation
+#[derive(Clone)]
 pub struct Generator {
     template_engine: TemplateEngine,
-    verbose: bool,
-    force_override: bool,
+    /// Where progress and results are reported
+    reporter: Arc<dyn ProgressReporter>,
+    conflict_policy: ConflictPolicy,
+    /// Whether missing variables may be asked for on the terminal
+    interactive: bool,
+    /// Fallback values (e.g. from the user config) for variables the structure doesn't set
+    variable_defaults: HashMap<String, String>,
+    /// Report what would happen without touching the filesystem
+    dry_run: bool,
+    /// Write a `.forge-tree.lock` manifest into the project root
+    lockfile: bool,
+    /// Structure file recorded in the manifest
+    source: Option<PathBuf>,
+    /// Worker threads rendering and writing files; 1 is sequential, 0 one per CPU
+    jobs: usize,
+    /// Run the structure's pre- and post-generate hooks
+    hooks: bool,
+    /// Turn the project into a git repository after writing it
+    git: Option<GitOptions>,
+}
+
+/// Counts of what a generation run did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GenerationSummary {
+    pub directories: usize,
+    pub created: usize,
+    pub skipped: usize,
+    pub overwritten: usize,
+    pub backed_up: usize,
+    /// Items (including their children) left out because their condition was false
+    pub excluded: usize,
+    /// Every file in tree order with what happened to it, relative to the output root
+    pub files: Vec<(PathBuf, FileOutcome)>,
+    /// Directories (including the project root) that did not exist before the run
+    pub new_directories: Vec<PathBuf>,
+}
+
+impl GenerationSummary {
+    fn record(&mut self, path: PathBuf, outcome: FileOutcome) {
+        match &outcome {
+            FileOutcome::Created => self.created += 1,
+            FileOutcome::Skipped => self.skipped += 1,
+            FileOutcome::Overwritten => self.overwritten += 1,
+            FileOutcome::BackedUp(_) => self.backed_up += 1,
+        }
+        self.files.push((path, outcome));
+    }
 }
 
 impl Generator {
     pub fn new() -> Self {
         Self {
             template_engine: TemplateEngine::new(),
-            verbose: false,
-            force_override: false,
+            reporter: Arc::new(SilentReporter),
+            conflict_policy: ConflictPolicy::default(),
+            interactive: false,
+            variable_defaults: HashMap::new(),
+            dry_run: false,
+            lockfile: false,
+            source: None,
+            jobs: 1,
+            hooks: false,
+            git: None,
         }
     }
 
-    pub fn with_verbose(mut self, verbose: bool) -> Self {
-        self.verbose = verbose;
+    /// Use a custom template engine, e.g. one with strict mode enabled
+    pub fn with_template_engine(mut self, template_engine: TemplateEngine) -> Self {
+        self.template_engine = template_engine;
+        self
+    }
+
+    /// Allow prompting for missing variables on the terminal
+    ///
+    /// When disabled, generation fails with a list of the missing variables.
+    pub fn with_interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+
+    /// Provide default values for variables the structure doesn't set
+    pub fn with_variable_defaults(mut self, defaults: HashMap<String, String>) -> Self {
+        self.variable_defaults = defaults;
+        self
+    }
+
+    /// Only report what would be created and which items are excluded
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
         self
     }
 
-    pub fn with_force_override(mut self, force: bool) -> Self {
-        self.force_override = force;
+    /// Report progress and results to `reporter` (silent by default)
+    pub fn with_reporter(mut self, reporter: Arc<dyn ProgressReporter>) -> Self {
+        self.reporter = reporter;
         self
     }
 
-    pub fn generate<P: AsRef<Path>>(&self, structure: &ProjectStructure, output_path: P) -> Result<()> {
-        let output_path = output_path.as_ref();
-        let total_items = self.count_items(&structure.items);
-        
-        // Create the root project directory
-        let root_path = output_path.join(&structure.root);
-        
-        // Create FileGenerator with force override setting
-        let file_generator = FileGenerator::new().with_force_overwrite(self.force_override);
-        file_generator.create_directory(&root_path)?;
-        
-        // Set up progress bar
-        let pb = ProgressBar::new(total_items as u64);
-        pb.set_style(
-            ProgressStyle::with_template(
-                "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} {msg}"
-            ).unwrap()
-            .progress_chars("█▉▊▋▌▍▎▏  ")
+    /// Write a `.forge-tree.lock` manifest of what was generated
+    pub fn with_lockfile(mut self, lockfile: bool) -> Self {
+        self.lockfile = lockfile;
+        self
+    }
+
+    /// Record the structure file (path and hash) in the manifest
+    pub fn with_source<P: Into<PathBuf>>(mut self, source: P) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Render and write files on a pool of `jobs` worker threads
+    ///
+    /// Directories are still created first, in tree order. `0` uses one
+    /// worker per CPU; the default of `1` generates sequentially.
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Run the structure's `pre_generate` and `post_generate` hooks
+    ///
+    /// Off by default: hooks run arbitrary commands, so callers should decide
+    /// whether the structure is trusted first. Hooks only run when generating
+    /// into a directory; dry runs report them without running them.
+    pub fn with_hooks(mut self, hooks: bool) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Initialise a git repository in the project root after writing it
+    ///
+    /// The structure's `.gitignore` patterns are written with the project.
+    /// Nothing is initialised or committed when the root is already inside a
+    /// repository or the output isn't a directory; post-generate hooks run
+    /// before the initial commit, so it includes their changes.
+    pub fn with_git(mut self, git: Option<GitOptions>) -> Self {
+        self.git = git;
+        self
+    }
+
+    /// Report on the terminal, listing every entry when `verbose`
+    ///
+    /// This installs a [`TerminalReporter`], which draws on stdout whatever
+    /// `verbose` is; pick a reporter with `with_reporter` instead.
+    #[deprecated(note = "use `with_reporter` with a `TerminalReporter`, `PlainReporter` or `SilentReporter`")]
+    pub fn with_verbose(self, verbose: bool) -> Self {
+        self.with_reporter(Arc::new(TerminalReporter::new(verbose)))
+    }
+
+    pub fn with_force_override(self, force: bool) -> Self {
+        let policy = if force { ConflictPolicy::Overwrite } else { ConflictPolicy::Error };
+        self.with_conflict_policy(policy)
+    }
+
+    /// Configure how files that already exist are handled
+    pub fn with_conflict_policy(mut self, policy: ConflictPolicy) -> Self {
+        self.conflict_policy = policy;
+        self
+    }
+
+    /// Every variable referenced by the root name, item names and templates
+    pub fn referenced_variables(&self, structure: &ProjectStructure) -> BTreeSet<String> {
+        let mut variables = TemplateEngine::referenced_variables(&structure.root);
+        collect_item_variables(&structure.items, &mut variables);
+        variables
+    }
+
+    /// Referenced or required variables that have neither a value nor a default
+    pub fn missing_variables(&self, structure: &ProjectStructure) -> Vec<String> {
+        self.needed_variables(structure)
+            .into_iter()
+            .filter(|name| {
+                !structure.variables.contains_key(name) && self.default_for(structure, name).is_none()
+            })
+            .collect()
+    }
+
+    /// Build the final variable set, prompting for missing values when interactive
+    ///
+    /// Values set on the structure win over defaults, and defaults from the
+    /// generator (e.g. user config) win over declared defaults. In interactive
+    /// mode every referenced or declared variable without a value is asked for,
+    /// offering the default if one exists; otherwise defaults are used silently
+    /// and anything still missing is an error. The result is validated against
+    /// the structure's declarations.
+    pub fn resolve_variables(&self, structure: &ProjectStructure) -> Result<HashMap<String, String>> {
+        let mut variables = structure.variables.clone();
+        let mut missing = Vec::new();
+        let needed = self.needed_variables(structure);
+
+        let candidates = needed.iter().chain(structure.declarations.keys()).collect::<BTreeSet<_>>();
+        for name in candidates {
+            if variables.contains_key(name) {
+                continue;
+            }
+
+            let default = self.default_for(structure, name);
+            if self.interactive {
+                let value = prompt_variable(name, structure.declarations.get(name), default.as_deref())?;
+                variables.insert(name.clone(), value);
+            } else if let Some(default) = default {
+                variables.insert(name.clone(), default);
+            } else if needed.contains(name) {
+                missing.push(name.clone());
+            }
+        }
+
+        if !missing.is_empty() {
+            return Err(ForgeTreeError::MissingVariables(missing.join(", ")));
+        }
+
+        variables::validate_values(&structure.declarations, &variables)?;
+        Ok(variables)
+    }
+
+    /// Variables that must have a value: referenced ones and required declarations
+    fn needed_variables(&self, structure: &ProjectStructure) -> BTreeSet<String> {
+        let mut needed = self.referenced_variables(structure);
+        needed.extend(
+            structure.declarations.iter()
+                .filter(|(_, decl)| decl.required)
+                .map(|(name, _)| name.clone())
         );
+        needed
+    }
+
+    fn default_for(&self, structure: &ProjectStructure, name: &str) -> Option<String> {
+        self.variable_defaults.get(name)
+            .cloned()
+            .or_else(|| structure.declarations.get(name).and_then(|decl| decl.default_string()))
+    }
+
+    /// Expand the structure into the concrete entries that would be generated
+    ///
+    /// Resolves variables (prompting if interactive), evaluates conditions,
+    /// instantiates loops and renders names and templates.
+    pub fn plan(&self, structure: &ProjectStructure) -> Result<GenerationPlan> {
+        let variables = self.resolve_variables(structure)?;
+        self.plan_with(structure, &variables)
+    }
+
+    /// Expand the structure using already resolved variables
+    fn plan_with(&self, structure: &ProjectStructure, variables: &HashMap<String, String>) -> Result<GenerationPlan> {
+        let context = variables::template_context(&structure.declarations, variables);
+        Planner::new(&self.template_engine).plan(&structure.root, &structure.items, &context)
+    }
+
+    /// Generate the project on the filesystem below `output_path`
+    pub fn generate<P: AsRef<Path>>(&self, structure: &ProjectStructure, output_path: P) -> Result<GenerationSummary> {
+        self.generate_into(structure, Arc::new(FsBackend::new(output_path.as_ref())))
+    }
+
+    /// Generate the project on the filesystem without blocking the async runtime
+    ///
+    /// Runs [`Generator::generate`] on tokio's blocking thread pool; combine
+    /// with [`Generator::with_jobs`] to also render and write files concurrently.
+    /// Must be called from within a tokio runtime.
+    pub async fn generate_async<P: AsRef<Path>>(
+        &self,
+        structure: &ProjectStructure,
+        output_path: P,
+    ) -> Result<GenerationSummary> {
+        let generator = self.clone();
+        let structure = structure.clone();
+        let output_path = output_path.as_ref().to_path_buf();
+
+        tokio::task::spawn_blocking(move || generator.generate(&structure, output_path))
+            .await
+            .map_err(|e| ForgeTreeError::Io(std::io::Error::other(e)))?
+    }
+
+    /// Generate the project through an output backend
+    ///
+    /// The project root is created as a top-level directory of the backend.
+    /// The backend's `finish` is called once everything has been written.
+    pub fn generate_into(&self, structure: &ProjectStructure, backend: Arc<dyn OutputBackend>) -> Result<GenerationSummary> {
+        let variables = self.resolve_variables(structure)?;
+        let (mut plan, pending) = self.prepare(structure, &variables)?;
+        let root_path = PathBuf::from(&plan.root);
+        let root_location = backend.location(&root_path);
+        self.reporter.started(&root_location, plan.entries.len() + 1, self.dry_run);
 
-        if self.verbose {
-            pb.println(format!("{} {}", "Created".green().bold(), root_path.display()));
+        let context = variables::template_context(&structure.declarations, &variables);
+        let hook_dir = self.hook_dir(structure, backend.as_ref(), &root_path);
+        let root_existed = backend.is_dir(&root_path);
+        if let Err(e) = self.run_hooks(HookStage::PreGenerate, structure, hook_dir.as_deref(), &variables) {
+            // Don't leave behind an empty root created just for the hooks
+            if let Some(dir) = hook_dir.as_ref().filter(|_| !root_existed) {
+                let _ = std::fs::remove_dir(dir);
+            }
+            return Err(e);
+        }
+
+        let run = Run {
+            // Create FileGenerator with the configured conflict policy
+            file_generator: FileGenerator::new()
+                .with_conflict_policy(self.conflict_policy)
+                .with_backend(backend.clone()),
+            backend: backend.as_ref(),
+            reporter: self.reporter.as_ref(),
+            dry_run: self.dry_run,
+        };
+        let mut summary = self.write(&mut plan, pending, &run)?;
+        if self.git.is_some() {
+            self.write_gitignore(structure, &context, &mut plan, &run, &mut summary)?;
+        }
+        // Pre-generate hooks need the root to run in, so it may already have been created
+        if !root_existed && !self.dry_run && !summary.new_directories.contains(&root_path) {
+            summary.new_directories.insert(0, root_path.clone());
         }
 
-        // Generate all items
-        self.generate_items(&structure.items, &root_path, &pb, &file_generator)?;
-        
-        pb.finish_with_message("Generation complete!");
-        
-        println!("\n{} Project '{}' forged successfully at {}", 
-                 "✅".green(), 
-                 structure.root.cyan().bold(), 
-                 root_path.canonicalize()
-                     .unwrap_or_else(|_| root_path.to_path_buf())
-                     .display());
+        if !self.dry_run {
+            if self.lockfile {
+                self.write_lockfile(&plan, &summary, &variables, backend.as_ref())?;
+            }
+            backend.finish()?;
+        }
+
+        let git_runner = GitRunner {
+            template_engine: &self.template_engine,
+            reporter: self.reporter.as_ref(),
+            context: &context,
+            dry_run: self.dry_run,
+        };
+        let git_dir = self.git_dir(&git_runner, backend.as_ref(), &root_path);
+        if let Some(dir) = &git_dir {
+            git_runner.init(dir)?;
+        }
+        self.run_hooks(HookStage::PostGenerate, structure, hook_dir.as_deref(), &variables)?;
+        if let (Some(dir), Some(options)) = (&git_dir, self.git.as_ref().filter(|options| options.commit)) {
+            git_runner.commit(dir, options, &structure.git)?;
+        }
+
+        self.reporter.finished(&root_location, &summary, self.dry_run);
+        Ok(summary)
+    }
 
+    /// Render the project in memory and return its directories and files
+    ///
+    /// Runs the same plan-and-write pipeline as [`Generator::generate`], but
+    /// into a [`MemoryBackend`] and without reporting anything. Dry-run,
+    /// reporter and conflict settings don't apply.
+    pub fn render(&self, structure: &ProjectStructure) -> Result<RenderedProject> {
+        let variables = self.resolve_variables(structure)?;
+        let (mut plan, pending) = self.prepare(structure, &variables)?;
+        let backend = Arc::new(MemoryBackend::new());
+
+        let run = Run {
+            file_generator: FileGenerator::new().with_backend(backend.clone()),
+            backend: backend.as_ref(),
+            reporter: &SilentReporter,
+            dry_run: false,
+        };
+        self.write(&mut plan, pending, &run)?;
+
+        Ok(RenderedProject::collect(&plan, &backend, &variables))
+    }
+
+    /// Directory hooks run in, or `None` if there are none to run or the backend has no directory
+    fn hook_dir(&self, structure: &ProjectStructure, backend: &dyn OutputBackend, root_path: &Path) -> Option<PathBuf> {
+        if !self.hooks || structure.hooks.is_empty() {
+            return None;
+        }
+
+        let dir = backend.local_path(root_path);
+        if dir.is_none() {
+            self.reporter.warning("Hooks were not run: the output is not a directory");
+        }
+        dir
+    }
+
+    /// Add the structure's `.gitignore` to the plan and write it, unless the project already has one
+    fn write_gitignore(
+        &self,
+        structure: &ProjectStructure,
+        context: &serde_json::Value,
+        plan: &mut GenerationPlan,
+        run: &Run,
+        summary: &mut GenerationSummary,
+    ) -> Result<()> {
+        let patterns = structure.git.ignore.iter()
+            .map(|pattern| self.template_engine.render_with_context(pattern, context))
+            .collect::<Result<Vec<_>>>()?;
+        let Some(content) = git::gitignore_content(&patterns) else {
+            return Ok(());
+        };
+
+        let root_path = Path::new(&plan.root).to_path_buf();
+        let path = PathBuf::from(GITIGNORE_NAME);
+        let generated = plan.entries.iter().any(|entry| entry.path() == path);
+        if generated || run.backend.exists(&root_path.join(&path)) {
+            return Ok(());
+        }
+
+        let entry = PlannedEntry::File { path, content: Some(content), mode: None };
+        self.generate_entry(&entry, &root_path, run, summary)?;
+        plan.entries.push(entry);
         Ok(())
     }
 
-    fn generate_items<P: AsRef<Path>>(
-        &self, 
-        items: &[crate::parser::StructureItem], 
-        base_path: P,
-        pb: &ProgressBar,
-        file_generator: &FileGenerator
+    /// Directory to initialise a repository in, or `None` if git isn't wanted or can't be used there
+    fn git_dir(&self, runner: &GitRunner, backend: &dyn OutputBackend, root_path: &Path) -> Option<PathBuf> {
+        self.git.as_ref()?;
+
+        let Some(dir) = backend.local_path(root_path) else {
+            self.reporter.warning("No git repository was initialised: the output is not a directory");
+            return None;
+        };
+        let dir = dir.canonicalize().unwrap_or(dir);
+        if runner.inside_repository(&dir) {
+            self.reporter.warning(&format!(
+                "{} is already inside a git repository; not initialising or committing",
+                dir.display()
+            ));
+            return None;
+        }
+        Some(dir)
+    }
+
+    fn run_hooks(
+        &self,
+        stage: HookStage,
+        structure: &ProjectStructure,
+        dir: Option<&Path>,
+        variables: &HashMap<String, String>,
     ) -> Result<()> {
-        let base_path = base_path.as_ref();
-
-        for item in items {
-            let item_path = base_path.join(&item.name);
-            
-            pb.inc(1);
-            pb.set_message(format!("Processing {}", item.name));
-            
-            match item.item_type {
-                crate::parser::ItemType::Directory => {
-                    file_generator.create_directory(&item_path)?;
-                    if self.verbose {
-                        pb.println(format!("{} {}", "Created".green().bold(), item_path.display()));
+        let hooks = structure.hooks.stage(stage);
+        let Some(dir) = dir.filter(|_| !hooks.is_empty()) else {
+            return Ok(());
+        };
+
+        if !self.dry_run {
+            std::fs::create_dir_all(dir)?;
+        }
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+
+        let runner = HookRunner {
+            reporter: self.reporter.as_ref(),
+            variables,
+            dry_run: self.dry_run,
+        };
+        runner.run(stage, hooks, &dir)
+    }
+
+    /// Write `.forge-tree.lock` into the project root
+    fn write_lockfile(
+        &self,
+        plan: &GenerationPlan,
+        summary: &GenerationSummary,
+        variables: &HashMap<String, String>,
+        backend: &dyn OutputBackend,
+    ) -> Result<()> {
+        let source = self.source.as_ref().map(LockSource::from_file).transpose()?;
+        let lockfile = Lockfile::new(plan, summary, variables, source);
+        backend.write_file(&Path::new(&plan.root).join(LOCKFILE_NAME), lockfile.to_json()?.as_bytes())
+    }
+
+    /// Plan the structure, leaving file templates to the workers when running concurrently
+    ///
+    /// Returns `None` for the pending renders when generating sequentially.
+    fn prepare(
+        &self,
+        structure: &ProjectStructure,
+        variables: &HashMap<String, String>,
+    ) -> Result<(GenerationPlan, Option<Vec<PendingRender>>)> {
+        if pool::worker_count(self.jobs) <= 1 || self.dry_run {
+            return Ok((self.plan_with(structure, variables)?, None));
+        }
+
+        let context = variables::template_context(&structure.declarations, variables);
+        match Planner::new(&self.template_engine).plan_deferred(&structure.root, &structure.items, &context) {
+            Ok((plan, pending)) => Ok((plan, Some(pending))),
+            // A full plan reports the error a sequential run would have hit first
+            Err(e) => Err(self.plan_with(structure, variables).err().unwrap_or(e)),
+        }
+    }
+
+    /// Write a prepared plan, concurrently if it has pending renders
+    fn write(&self, plan: &mut GenerationPlan, pending: Option<Vec<PendingRender>>, run: &Run) -> Result<GenerationSummary> {
+        match pending {
+            Some(pending) => self.write_plan_concurrent(plan, pending, run),
+            None => self.write_plan(plan, run),
+        }
+    }
+
+    /// Create the root and every planned entry in tree order
+    fn write_plan(&self, plan: &GenerationPlan, run: &Run) -> Result<GenerationSummary> {
+        let root_path = Path::new(&plan.root);
+        let mut summary = GenerationSummary::default();
+        self.create_root(root_path, run, &mut summary)?;
+
+        for entry in &plan.entries {
+            self.generate_entry(entry, root_path, run, &mut summary)?;
+        }
+        Ok(summary)
+    }
+
+    /// Create the root and directories in tree order, then render and write files on the worker pool
+    ///
+    /// Rendered contents are stored back into the plan. If several entries
+    /// fail, the error of the first one in tree order is returned; files
+    /// after a failing directory are never started.
+    fn write_plan_concurrent(
+        &self,
+        plan: &mut GenerationPlan,
+        pending: Vec<PendingRender>,
+        run: &Run,
+    ) -> Result<GenerationSummary> {
+        let root_path = PathBuf::from(&plan.root);
+        let mut summary = GenerationSummary::default();
+        self.create_root(&root_path, run, &mut summary)?;
+
+        let mut directory_error = None;
+        let mut files = Vec::new();
+        for (index, entry) in plan.entries.iter().enumerate() {
+            match entry {
+                PlannedEntry::File { .. } => files.push(index),
+                _ => {
+                    if let Err(e) = self.generate_entry(entry, &root_path, run, &mut summary) {
+                        directory_error = Some(e);
+                        break;
                     }
-                    
-                    self.generate_items(&item.children, &item_path, pb, file_generator)?;
                 }
-                crate::parser::ItemType::File => {
-                    let content = if let Some(template) = &item.template {
-                        self.template_engine.render_template(template, &std::collections::HashMap::new())?
-                    } else {
-                        item.content.clone().unwrap_or_default()
-                    };
-                    
-                    file_generator.create_file(&item_path, &content)?;
-                    if self.verbose {
-                        pb.println(format!("{} {}", "Created".blue().bold(), item_path.display()));
+            }
+        }
+
+        let templates: HashMap<usize, &PendingRender> = pending.iter().map(|render| (render.entry, render)).collect();
+        let entries = &plan.entries;
+        let written = pool::run_ordered(&files, pool::worker_count(self.jobs), |&index| {
+            let PlannedEntry::File { path, content, mode } = &entries[index] else {
+                unreachable!("only files are queued");
+            };
+            let rendered = templates.get(&index).map(|render| render.render(&self.template_engine)).transpose()?;
+
+            let relative_path = root_path.join(path);
+            let content = rendered.as_deref().or(content.as_deref()).unwrap_or_default();
+            let outcome = self.write_file(&relative_path, content, *mode, run)?;
+            Ok((relative_path, outcome, rendered))
+        })?;
+
+        if let Some(e) = directory_error {
+            return Err(e);
+        }
+
+        for (index, (relative_path, outcome, rendered)) in files.into_iter().zip(written) {
+            summary.record(relative_path, outcome);
+            if let (Some(rendered), PlannedEntry::File { content, .. }) = (rendered, &mut plan.entries[index]) {
+                *content = Some(rendered);
+            }
+        }
+        Ok(summary)
+    }
+
+    fn create_root(&self, root_path: &Path, run: &Run, summary: &mut GenerationSummary) -> Result<()> {
+        if !run.dry_run {
+            if !run.backend.is_dir(root_path) {
+                summary.new_directories.push(root_path.to_path_buf());
+            }
+            run.file_generator.create_directory(root_path)?;
+        }
+        run.reporter.item_created(&run.backend.location(root_path), &ItemType::Directory);
+        Ok(())
+    }
+
+    fn generate_entry(
+        &self,
+        entry: &PlannedEntry,
+        root_path: &Path,
+        run: &Run,
+        summary: &mut GenerationSummary,
+    ) -> Result<()> {
+        let relative_path = root_path.join(entry.path());
+        let item_path = run.backend.location(&relative_path);
+
+        match entry {
+            PlannedEntry::Excluded { reason, items, .. } => {
+                summary.excluded += items;
+                run.reporter.item_skipped(&item_path, &SkipReason::Excluded { condition: reason.clone(), items: *items });
+            }
+            PlannedEntry::Directory { mode, .. } => {
+                summary.directories += 1;
+                if !run.dry_run {
+                    if !run.backend.is_dir(&relative_path) {
+                        summary.new_directories.push(relative_path.clone());
+                    }
+                    run.file_generator.create_directory(&relative_path)?;
+                    if let Some(mode) = mode {
+                        run.backend.set_permissions(&relative_path, *mode)?;
                     }
                 }
+                run.reporter.item_created(&item_path, &ItemType::Directory);
+            }
+            PlannedEntry::File { content, mode, .. } => {
+                if run.dry_run {
+                    summary.record(relative_path, FileOutcome::Created);
+                    run.reporter.item_created(&item_path, &ItemType::File);
+                    return Ok(());
+                }
+
+                let outcome = self.write_file(&relative_path, content.as_deref().unwrap_or_default(), *mode, run)?;
+                summary.record(relative_path, outcome);
+            }
+            PlannedEntry::Symlink { target, .. } => {
+                if run.dry_run {
+                    summary.record(relative_path, FileOutcome::Created);
+                    let item_type = ItemType::Symlink { target: target.to_string_lossy().into_owned() };
+                    run.reporter.item_created(&item_path, &item_type);
+                    return Ok(());
+                }
+
+                let outcome = self.write_symlink(&relative_path, target, run)?;
+                summary.record(relative_path, outcome);
             }
         }
 
         Ok(())
     }
 
-    fn count_items(&self, items: &[crate::parser::StructureItem]) -> usize {
-        let mut count = items.len();
-        for item in items {
-            count += self.count_items(&item.children);
+    /// Create a single file, apply its mode and report what happened to it
+    fn write_file(&self, relative_path: &Path, content: &str, mode: Option<u32>, run: &Run) -> Result<FileOutcome> {
+        let outcome = self.create_entry(run, || run.file_generator.create_file(relative_path, content))?;
+        if let Some(mode) = mode.filter(|_| outcome != FileOutcome::Skipped) {
+            run.backend.set_permissions(relative_path, mode)?;
+        }
+
+        self.report_outcome(relative_path, &outcome, &ItemType::File, run);
+        Ok(outcome)
+    }
+
+    /// Create a single symlink and report what happened to it
+    fn write_symlink(&self, relative_path: &Path, target: &Path, run: &Run) -> Result<FileOutcome> {
+        let outcome = self.create_entry(run, || run.file_generator.create_symlink(relative_path, target))?;
+
+        let item_type = ItemType::Symlink { target: target.to_string_lossy().into_owned() };
+        self.report_outcome(relative_path, &outcome, &item_type, run);
+        Ok(outcome)
+    }
+
+    /// Run `create`, pausing the progress display if it may prompt about a conflict
+    fn create_entry<F>(&self, run: &Run, mut create: F) -> Result<FileOutcome>
+    where
+        F: FnMut() -> Result<FileOutcome>,
+    {
+        if self.conflict_policy != ConflictPolicy::Prompt {
+            return create();
+        }
+
+        // Keep prompts readable by pausing the progress display while asking
+        let mut result = None;
+        run.reporter.suspend(&mut || result = Some(create()));
+        result.expect("reporters run the suspended closure")
+    }
+
+    fn report_outcome(&self, relative_path: &Path, outcome: &FileOutcome, item_type: &ItemType, run: &Run) {
+        let item_path = run.backend.location(relative_path);
+        match outcome {
+            FileOutcome::Created => run.reporter.item_created(&item_path, item_type),
+            FileOutcome::Skipped => run.reporter.item_skipped(&item_path, &SkipReason::Exists),
+            FileOutcome::Overwritten => run.reporter.item_replaced(&item_path, None),
+            FileOutcome::BackedUp(backup) => {
+                run.reporter.item_replaced(&item_path, Some(&run.backend.location(backup)));
+            }
+        }
+    }
+}
+
+/// Per-run state for writing a plan
+struct Run<'a> {
+    file_generator: FileGenerator,
+    backend: &'a dyn OutputBackend,
+    reporter: &'a dyn ProgressReporter,
+    dry_run: bool,
+}
+
+fn collect_item_variables(items: &[StructureItem], variables: &mut BTreeSet<String>) {
+    for item in items {
+        let mut subtree = TemplateEngine::referenced_variables(&item.name);
+        if let Some(template) = &item.template {
+            subtree.extend(TemplateEngine::referenced_variables(template));
+        }
+        collect_item_variables(&item.children, &mut subtree);
+
+        // Inside a loop `item` is bound by the loop, and the list itself is required
+        if let Some(list) = &item.each {
+            subtree.remove(LOOP_ITEM);
+            subtree.insert(list.clone());
+        }
+        variables.extend(subtree);
+    }
+}
+
+/// Ask for a variable value until a valid answer (or the default) is given
+fn prompt_variable(name: &str, decl: Option<&VariableDecl>, default: Option<&str>) -> Result<String> {
+    if let Some(description) = decl.and_then(|decl| decl.description.as_ref()) {
+        eprintln!("{} {}", "?".cyan().bold(), description);
+    }
+
+    let hint = match decl {
+        Some(decl) if decl.var_type == VarType::Enum => format!(" ({})", decl.values.join("/")),
+        Some(decl) if decl.var_type != VarType::String => format!(" ({})", decl.var_type),
+        _ => String::new(),
+    };
+
+    loop {
+        let question = match default {
+            Some(default) => format!("{}{} [{}]:", name.cyan(), hint, default),
+            None => format!("{}{}:", name.cyan(), hint),
+        };
+        let answer = prompt::ask(&question)?;
+
+        let value = match (answer.is_empty(), default) {
+            (false, _) => answer,
+            (true, Some(default)) => default.to_string(),
+            (true, None) => {
+                eprintln!("{} A value is required for '{}'", "⚠️".yellow(), name);
+                continue;
+            }
+        };
+
+        match decl.map(|decl| decl.check(&value)) {
+            Some(Err(e)) => eprintln!("{} {}", "⚠️".yellow(), e),
+            _ => return Ok(value),
         }
-        count
     }
 }
 
@@ -0,0 +1,334 @@
+//! Expansion of a structure into a concrete list of entries
+//!
+//! Planning evaluates conditions, instantiates `[each list]` loops and renders
+//! names and templates, producing the exact directories and files to create in
+//! tree order. Nothing touches the filesystem until the plan is executed.
+
+use crate::generator::TemplateEngine;
+use crate::parser::{ItemType, StructureItem, StructureStats};
+use crate::{ForgeTreeError, Result};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+/// Context variable holding the current element inside an `[each list]` loop
+pub const LOOP_ITEM: &str = "item";
+
+/// A concrete entry to generate, with a path relative to the project root
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlannedEntry {
+    Directory {
+        path: PathBuf,
+        /// Permission bits to set after creating it
+        mode: Option<u32>,
+    },
+    File {
+        path: PathBuf,
+        /// Rendered contents, or `None` if the structure gives none and the file is created empty
+        content: Option<String>,
+        /// Permission bits to set after writing it
+        mode: Option<u32>,
+    },
+    Symlink {
+        path: PathBuf,
+        /// Rendered target, relative to the link's directory unless absolute
+        target: PathBuf,
+    },
+    /// An item left out because its condition was false
+    Excluded {
+        path: PathBuf,
+        /// The condition that failed, e.g. `if docker`
+        reason: String,
+        /// Number of items skipped, including the item's children
+        items: usize,
+    },
+}
+
+impl PlannedEntry {
+    pub fn path(&self) -> &Path {
+        match self {
+            PlannedEntry::Directory { path, .. }
+            | PlannedEntry::File { path, .. }
+            | PlannedEntry::Symlink { path, .. }
+            | PlannedEntry::Excluded { path, .. } => path,
+        }
+    }
+}
+
+/// The fully expanded structure
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerationPlan {
+    /// Rendered name of the project root directory
+    pub root: String,
+    /// Entries in tree order; parents always come before their children
+    pub entries: Vec<PlannedEntry>,
+}
+
+/// A file template left for a worker to render
+pub(crate) struct PendingRender {
+    /// Position of the file in `GenerationPlan::entries`
+    pub(crate) entry: usize,
+    template: String,
+    scope: Scope,
+}
+
+impl PendingRender {
+    pub(crate) fn render(&self, template_engine: &TemplateEngine) -> Result<String> {
+        render(template_engine, &self.template, &self.scope)
+    }
+}
+
+/// Entries expanded so far, and the templates deferred while expanding them
+struct Expansion {
+    entries: Vec<PlannedEntry>,
+    /// `None` renders templates in place
+    pending: Option<Vec<PendingRender>>,
+}
+
+/// Variables in scope while expanding a subtree
+#[derive(Clone)]
+struct Scope {
+    context: Value,
+    /// Zero-based position in the innermost loop, exposed as `{{@index}}`
+    index: Option<usize>,
+}
+
+pub(crate) struct Planner<'a> {
+    template_engine: &'a TemplateEngine,
+}
+
+impl<'a> Planner<'a> {
+    pub(crate) fn new(template_engine: &'a TemplateEngine) -> Self {
+        Self { template_engine }
+    }
+
+    pub(crate) fn plan(&self, root: &str, items: &[StructureItem], context: &Value) -> Result<GenerationPlan> {
+        let (plan, _) = self.expand(root, items, context, None)?;
+        Ok(plan)
+    }
+
+    /// Like [`Planner::plan`], but leaves file templates unrendered
+    ///
+    /// Files with a template get empty content in the plan; the returned
+    /// renders fill them in, in any order.
+    pub(crate) fn plan_deferred(
+        &self,
+        root: &str,
+        items: &[StructureItem],
+        context: &Value,
+    ) -> Result<(GenerationPlan, Vec<PendingRender>)> {
+        let (plan, pending) = self.expand(root, items, context, Some(Vec::new()))?;
+        Ok((plan, pending.unwrap_or_default()))
+    }
+
+    fn expand(
+        &self,
+        root: &str,
+        items: &[StructureItem],
+        context: &Value,
+        pending: Option<Vec<PendingRender>>,
+    ) -> Result<(GenerationPlan, Option<Vec<PendingRender>>)> {
+        let scope = Scope { context: context.clone(), index: None };
+        let root = self.render_name(root, &scope)?;
+
+        let mut out = Expansion { entries: Vec::new(), pending };
+        self.expand_items(items, Path::new(""), &scope, &mut out)?;
+        let Expansion { entries, pending } = out;
+
+        // Loops and templated names can render two entries to the same path
+        let mut seen = HashSet::new();
+        for entry in &entries {
+            if !matches!(entry, PlannedEntry::Excluded { .. }) && !seen.insert(entry.path()) {
+                return Err(ForgeTreeError::DuplicateEntry(format!(
+                    "'{}' is generated more than once", entry.path().display()
+                )));
+            }
+        }
+        check_link_targets(&entries)?;
+
+        Ok((GenerationPlan { root, entries }, pending))
+    }
+
+    fn expand_items(&self, items: &[StructureItem], parent: &Path, scope: &Scope, out: &mut Expansion) -> Result<()> {
+        for item in items {
+            // A false condition excludes the item and every loop instance of it
+            if let Some(failed) = item.condition.as_ref().and_then(|condition| condition.first_failure(&scope.context)) {
+                out.entries.push(PlannedEntry::Excluded {
+                    path: parent.join(self.display_name(item, scope)),
+                    reason: failed.to_string(),
+                    items: 1 + StructureStats::of(&item.children).total(),
+                });
+                continue;
+            }
+
+            match &item.each {
+                Some(list) => {
+                    for (index, element) in self.loop_elements(list, &scope.context)?.into_iter().enumerate() {
+                        let mut context = scope.context.clone();
+                        if let Value::Object(map) = &mut context {
+                            map.insert(LOOP_ITEM.to_string(), element);
+                        }
+                        let loop_scope = Scope { context, index: Some(index) };
+                        self.expand_item(item, parent, &loop_scope, out)?;
+                    }
+                }
+                None => self.expand_item(item, parent, scope, out)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn expand_item(&self, item: &StructureItem, parent: &Path, scope: &Scope, out: &mut Expansion) -> Result<()> {
+        let path = parent.join(self.render_name(&item.name, scope)?);
+
+        match &item.item_type {
+            ItemType::Directory => {
+                out.entries.push(PlannedEntry::Directory { path: path.clone(), mode: item.mode });
+                self.expand_items(&item.children, &path, scope, out)?;
+            }
+            ItemType::File => {
+                let content = match (&item.template, &mut out.pending) {
+                    (Some(template), Some(pending)) => {
+                        pending.push(PendingRender {
+                            entry: out.entries.len(),
+                            template: template.clone(),
+                            scope: scope.clone(),
+                        });
+                        Some(String::new())
+                    }
+                    (Some(template), None) => Some(render(self.template_engine, template, scope)?),
+                    (None, _) => item.content.clone(),
+                };
+                out.entries.push(PlannedEntry::File { path, content, mode: item.mode });
+            }
+            ItemType::Symlink { target } => {
+                let target = PathBuf::from(self.render_target(target, scope)?);
+                out.entries.push(PlannedEntry::Symlink { path, target });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The elements an `[each list]` entry iterates over
+    ///
+    /// Typed `list` variables are arrays; plain string values are split on commas.
+    fn loop_elements(&self, list: &str, context: &Value) -> Result<Vec<Value>> {
+        match context.get(list) {
+            Some(Value::Array(elements)) => Ok(elements.clone()),
+            Some(Value::String(value)) => Ok(value.split(',')
+                .map(str::trim)
+                .filter(|element| !element.is_empty())
+                .map(|element| Value::String(element.to_string()))
+                .collect()),
+            Some(other) => Err(ForgeTreeError::InvalidVariable(format!(
+                "{}: cannot loop over {}", list, other
+            ))),
+            None => Err(ForgeTreeError::MissingVariables(list.to_string())),
+        }
+    }
+
+    /// Name used when reporting an excluded item; falls back to the raw name if it can't render
+    fn display_name(&self, item: &StructureItem, scope: &Scope) -> String {
+        self.render_name(&item.name, scope).unwrap_or_else(|_| item.name.clone())
+    }
+
+    /// Render variables in a symlink target, leaving plain targets untouched
+    fn render_target(&self, target: &str, scope: &Scope) -> Result<String> {
+        if !target.contains("{{") {
+            return Ok(target.to_string());
+        }
+        render(self.template_engine, target, scope)
+    }
+
+    /// Render variables in an entry name, leaving plain names untouched
+    fn render_name(&self, name: &str, scope: &Scope) -> Result<String> {
+        if !name.contains("{{") {
+            return Ok(name.to_string());
+        }
+
+        let rendered = render(self.template_engine, name, scope)?;
+        if rendered.trim().is_empty() || rendered.contains(['/', '\\']) || rendered == ".." {
+            return Err(ForgeTreeError::InvalidPath(format!(
+                "Name '{}' rendered to invalid name '{}'", name, rendered
+            )));
+        }
+        Ok(rendered)
+    }
+}
+
+/// Reject relative symlink targets that lead out of the project root
+///
+/// Targets are followed through the other planned links, so `sub/a -> ..`
+/// and `sub/b -> a/../..` can't combine to escape. Absolute targets are
+/// allowed as written.
+fn check_link_targets(entries: &[PlannedEntry]) -> Result<()> {
+    let links: HashMap<&Path, &Path> = entries.iter()
+        .filter_map(|entry| match entry {
+            PlannedEntry::Symlink { path, target } => Some((path.as_path(), target.as_path())),
+            _ => None,
+        })
+        .collect();
+
+    for entry in entries {
+        if let PlannedEntry::Symlink { path, target } = entry {
+            let dir = path.parent().unwrap_or(Path::new(""));
+            if let LinkTarget::Outside = resolve_link_target(&links, dir, target, 0) {
+                return Err(ForgeTreeError::InvalidPath(format!(
+                    "Symlink '{}' points outside the project root: {}", path.display(), target.display()
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Where a relative link target leads
+enum LinkTarget {
+    /// A path relative to the project root
+    Inside(PathBuf),
+    /// An absolute path, or round a loop of links: nothing left to check
+    Unchecked,
+    /// Above the project root
+    Outside,
+}
+
+/// Follow `target` from `dir`, continuing through any planned link on the way
+fn resolve_link_target(links: &HashMap<&Path, &Path>, dir: &Path, target: &Path, hops: usize) -> LinkTarget {
+    if target.has_root() || hops > links.len() {
+        return LinkTarget::Unchecked;
+    }
+
+    let mut current = dir.to_path_buf();
+    let mut components = target.components().peekable();
+    while let Some(component) = components.next() {
+        match component {
+            Component::ParentDir if current.as_os_str().is_empty() => return LinkTarget::Outside,
+            Component::ParentDir => {
+                current.pop();
+            }
+            Component::Normal(name) => {
+                current.push(name);
+                // The rest of the target continues from wherever a link on the way points
+                let Some(next) = links.get(current.as_path()).filter(|_| components.peek().is_some()) else {
+                    continue;
+                };
+                current.pop();
+                match resolve_link_target(links, &current, next, hops + 1) {
+                    LinkTarget::Inside(resolved) => current = resolved,
+                    other => return other,
+                }
+            }
+            _ => {}
+        }
+    }
+    LinkTarget::Inside(current)
+}
+
+fn render(template_engine: &TemplateEngine, template: &str, scope: &Scope) -> Result<String> {
+    match scope.index {
+        Some(index) => template_engine.render_with_index(template, &scope.context, index),
+        None => template_engine.render_with_context(template, &scope.context),
+    }
+}
@@ -0,0 +1,57 @@
+//! Bounded worker pool for rendering and writing files
+//!
+//! Jobs are handed out in order to a fixed number of scoped threads. Once a
+//! job fails, no later job is started, and the error reported is always the
+//! one from the earliest failing job, no matter which thread finished first.
+
+use crate::Result;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Number of workers to use for a requested job count; 0 means one per CPU
+pub fn worker_count(jobs: usize) -> usize {
+    match jobs {
+        0 => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        jobs => jobs,
+    }
+}
+
+/// Run `task` on every job using up to `workers` threads
+///
+/// Results come back in job order. On failure, the error of the first failing
+/// job (by position) is returned; jobs before it have all completed, jobs
+/// after it may or may not have run.
+pub(crate) fn run_ordered<T, R, F>(jobs: &[T], workers: usize, task: F) -> Result<Vec<R>>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> Result<R> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let first_failure = AtomicUsize::new(usize::MAX);
+    let slots: Vec<Mutex<Option<Result<R>>>> = jobs.iter().map(|_| Mutex::new(None)).collect();
+
+    thread::scope(|scope| {
+        for _ in 0..workers.clamp(1, jobs.len().max(1)) {
+            scope.spawn(|| loop {
+                // Jobs are claimed in order, so every job before a failure has been claimed
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                if index >= jobs.len() || index > first_failure.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                let result = task(&jobs[index]);
+                if result.is_err() {
+                    first_failure.fetch_min(index, Ordering::SeqCst);
+                }
+                *slots[index].lock().unwrap() = Some(result);
+            });
+        }
+    });
+
+    // Unfilled slots only follow a failure, so the first error is reached first
+    slots.into_iter()
+        .map(|slot| slot.into_inner().unwrap().expect("jobs before the first failure always run"))
+        .collect()
+}
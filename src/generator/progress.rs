@@ -0,0 +1,342 @@
+//! Progress and result reporting for generation runs
+//!
+//! The generator itself never prints. It reports what it does to a
+//! [`ProgressReporter`], and the caller decides whether that becomes a
+//! progress bar, plain log lines, JSON lines or nothing at all.
+
+use crate::generator::GenerationSummary;
+use crate::hooks::HookStage;
+use crate::parser::ItemType;
+use colored::*;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Why an entry was not written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The file already existed and the conflict policy kept it
+    Exists,
+    /// The entry's condition was false
+    Excluded {
+        /// The condition that failed, e.g. `if docker`
+        condition: String,
+        /// Number of items left out, including the entry's children
+        items: usize,
+    },
+}
+
+/// Receives events while a project is generated
+///
+/// Paths are where the entry ends up, as shown by the output backend. Events
+/// for files may arrive from several threads when generating with more than
+/// one job. Every method does nothing by default.
+pub trait ProgressReporter: Send + Sync {
+    /// Generation of `total` entries (including the root) is about to start
+    fn started(&self, _root: &Path, _total: usize, _dry_run: bool) {}
+
+    /// A directory or file was created, or would be in a dry run
+    fn item_created(&self, _path: &Path, _item_type: &ItemType) {}
+
+    /// An existing file was replaced, after being moved to `backup` if given
+    fn item_replaced(&self, _path: &Path, _backup: Option<&Path>) {}
+
+    /// An entry was left out
+    fn item_skipped(&self, _path: &Path, _reason: &SkipReason) {}
+
+    /// A hook command is about to run, or would run in a dry run
+    fn hook_started(&self, _stage: HookStage, _command: &str) {}
+
+    /// A git command is about to run, or would run in a dry run
+    fn git_started(&self, _command: &str) {}
+
+    /// Something worth pointing out that doesn't stop generation
+    fn warning(&self, _message: &str) {}
+
+    /// Everything has been written (or listed, in a dry run)
+    fn finished(&self, _root: &Path, _summary: &GenerationSummary, _dry_run: bool) {}
+
+    /// Run `f` with any live display paused, e.g. while prompting on the terminal
+    fn suspend(&self, f: &mut dyn FnMut()) {
+        f()
+    }
+}
+
+/// Reports nothing; the default for library use
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SilentReporter;
+
+impl ProgressReporter for SilentReporter {}
+
+/// Progress bar on stderr with colored lines for an interactive terminal
+///
+/// Each entry is listed when verbose; dry runs always list every entry and
+/// show no bar.
+pub struct TerminalReporter {
+    verbose: bool,
+    dry_run: AtomicBool,
+    bar: ProgressBar,
+}
+
+impl TerminalReporter {
+    pub fn new(verbose: bool) -> Self {
+        let bar = ProgressBar::hidden();
+        bar.set_style(
+            ProgressStyle::with_template(
+                "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} {msg}"
+            ).unwrap()
+            .progress_chars("█▉▊▋▌▍▎▏  ")
+        );
+        Self { verbose, dry_run: AtomicBool::new(false), bar }
+    }
+
+    fn dry_run(&self) -> bool {
+        self.dry_run.load(Ordering::Relaxed)
+    }
+
+    fn advance(&self, path: &Path) {
+        self.bar.inc(1);
+        self.bar.set_message(format!("Processing {}", path.display()));
+    }
+
+    /// Print a line above the progress bar, or directly when the bar is hidden
+    fn log(&self, message: String) {
+        if self.bar.is_hidden() {
+            println!("{}", message);
+        } else {
+            self.bar.println(message);
+        }
+    }
+}
+
+impl ProgressReporter for TerminalReporter {
+    fn started(&self, _root: &Path, total: usize, dry_run: bool) {
+        self.dry_run.store(dry_run, Ordering::Relaxed);
+        self.bar.set_length(total as u64);
+        if !dry_run {
+            self.bar.set_draw_target(ProgressDrawTarget::stderr());
+        }
+    }
+
+    fn item_created(&self, path: &Path, item_type: &ItemType) {
+        self.advance(path);
+        if self.dry_run() {
+            self.log(format!("{} {}", "Would create".green().bold(), path.display()));
+        } else if self.verbose {
+            let label = match item_type {
+                ItemType::Directory => "Created".green().bold(),
+                ItemType::File => "Created".blue().bold(),
+                ItemType::Symlink { .. } => "Linked".cyan().bold(),
+            };
+            self.log(format!("{} {}", label, path.display()));
+        }
+    }
+
+    fn item_replaced(&self, path: &Path, backup: Option<&Path>) {
+        self.advance(path);
+        if self.verbose {
+            let label = match backup {
+                Some(_) => "Backed up".magenta().bold(),
+                None => "Overwrote".magenta().bold(),
+            };
+            self.log(format!("{} {}", label, path.display()));
+        }
+    }
+
+    fn item_skipped(&self, path: &Path, reason: &SkipReason) {
+        self.advance(path);
+        match reason {
+            SkipReason::Exists if self.verbose => {
+                self.log(format!("{} {}", "Skipped".yellow().bold(), path.display()));
+            }
+            SkipReason::Excluded { condition, .. } if self.verbose || self.dry_run() => {
+                self.log(format!("{} {} ({} is false)", "Excluded".yellow().bold(), path.display(), condition));
+            }
+            _ => {}
+        }
+    }
+
+    fn hook_started(&self, stage: HookStage, command: &str) {
+        let label = if self.dry_run() { "Would run" } else { "Running" };
+        self.log(format!("{} {} ({})", label.cyan().bold(), command, stage));
+    }
+
+    fn git_started(&self, command: &str) {
+        let label = if self.dry_run() { "Would run" } else { "Running" };
+        self.log(format!("{} {}", label.cyan().bold(), command));
+    }
+
+    fn warning(&self, message: &str) {
+        self.bar.suspend(|| eprintln!("{} {}", "⚠️".yellow(), message));
+    }
+
+    fn finished(&self, root: &Path, summary: &GenerationSummary, dry_run: bool) {
+        self.bar.finish_with_message("Generation complete!");
+
+        if dry_run {
+            println!("\n{} Dry run: {} directories and {} files would be created, {} items excluded",
+                     "📝".cyan(), summary.directories + 1, summary.created, summary.excluded);
+            return;
+        }
+
+        let name = root.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        println!("\n{} Project '{}' forged successfully at {}",
+                 "✅".green(),
+                 name.cyan().bold(),
+                 root.canonicalize().unwrap_or_else(|_| root.to_path_buf()).display());
+        println!("   {} created, {} skipped, {} overwritten, {} backed up",
+                 summary.created, summary.skipped, summary.overwritten, summary.backed_up);
+    }
+
+    fn suspend(&self, f: &mut dyn FnMut()) {
+        self.bar.suspend(f)
+    }
+}
+
+/// Uncolored log lines on stdout, for pipes and CI logs
+///
+/// Lists the same entries as [`TerminalReporter`], without a bar.
+pub struct PlainReporter {
+    verbose: bool,
+    dry_run: AtomicBool,
+}
+
+impl PlainReporter {
+    pub fn new(verbose: bool) -> Self {
+        Self { verbose, dry_run: AtomicBool::new(false) }
+    }
+
+    fn dry_run(&self) -> bool {
+        self.dry_run.load(Ordering::Relaxed)
+    }
+}
+
+impl ProgressReporter for PlainReporter {
+    fn started(&self, _root: &Path, _total: usize, dry_run: bool) {
+        self.dry_run.store(dry_run, Ordering::Relaxed);
+    }
+
+    fn item_created(&self, path: &Path, _item_type: &ItemType) {
+        if self.dry_run() {
+            println!("would create {}", path.display());
+        } else if self.verbose {
+            println!("created {}", path.display());
+        }
+    }
+
+    fn item_replaced(&self, path: &Path, backup: Option<&Path>) {
+        if !self.verbose {
+            return;
+        }
+        match backup {
+            Some(backup) => println!("backed up {} to {}", path.display(), backup.display()),
+            None => println!("overwrote {}", path.display()),
+        }
+    }
+
+    fn item_skipped(&self, path: &Path, reason: &SkipReason) {
+        match reason {
+            SkipReason::Exists if self.verbose => println!("skipped {} (exists)", path.display()),
+            SkipReason::Excluded { condition, .. } if self.verbose || self.dry_run() => {
+                println!("excluded {} ({} is false)", path.display(), condition);
+            }
+            _ => {}
+        }
+    }
+
+    fn hook_started(&self, stage: HookStage, command: &str) {
+        let label = if self.dry_run() { "would run" } else { "running" };
+        println!("{} {} hook: {}", label, stage, command);
+    }
+
+    fn git_started(&self, command: &str) {
+        let label = if self.dry_run() { "would run" } else { "running" };
+        println!("{} {}", label, command);
+    }
+
+    fn warning(&self, message: &str) {
+        eprintln!("warning: {}", message);
+    }
+
+    fn finished(&self, root: &Path, summary: &GenerationSummary, dry_run: bool) {
+        if dry_run {
+            println!("dry run: {} directories and {} files would be created, {} items excluded",
+                     summary.directories + 1, summary.created, summary.excluded);
+        } else {
+            println!("forged {}: {} created, {} skipped, {} overwritten, {} backed up",
+                     root.display(), summary.created, summary.skipped, summary.overwritten, summary.backed_up);
+        }
+    }
+}
+
+/// One JSON object per event on stdout, for tools following along
+///
+/// Every object has an `event` field named after the trait method.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonLinesReporter;
+
+impl JsonLinesReporter {
+    fn emit(&self, event: Value) {
+        println!("{}", event);
+    }
+}
+
+impl ProgressReporter for JsonLinesReporter {
+    fn started(&self, root: &Path, total: usize, dry_run: bool) {
+        self.emit(json!({ "event": "started", "root": root, "total": total, "dry_run": dry_run }));
+    }
+
+    fn item_created(&self, path: &Path, item_type: &ItemType) {
+        let kind = match item_type {
+            ItemType::Directory => "directory",
+            ItemType::File => "file",
+            ItemType::Symlink { .. } => "symlink",
+        };
+        self.emit(json!({ "event": "item_created", "path": path, "kind": kind }));
+    }
+
+    fn item_replaced(&self, path: &Path, backup: Option<&Path>) {
+        self.emit(json!({ "event": "item_replaced", "path": path, "backup": backup }));
+    }
+
+    fn item_skipped(&self, path: &Path, reason: &SkipReason) {
+        let event = match reason {
+            SkipReason::Exists => json!({ "event": "item_skipped", "path": path, "reason": "exists" }),
+            SkipReason::Excluded { condition, items } => json!({
+                "event": "item_skipped",
+                "path": path,
+                "reason": "excluded",
+                "condition": condition,
+                "items": items,
+            }),
+        };
+        self.emit(event);
+    }
+
+    fn hook_started(&self, stage: HookStage, command: &str) {
+        self.emit(json!({ "event": "hook_started", "stage": stage.to_string(), "command": command }));
+    }
+
+    fn git_started(&self, command: &str) {
+        self.emit(json!({ "event": "git_started", "command": command }));
+    }
+
+    fn warning(&self, message: &str) {
+        self.emit(json!({ "event": "warning", "message": message }));
+    }
+
+    fn finished(&self, root: &Path, summary: &GenerationSummary, dry_run: bool) {
+        self.emit(json!({
+            "event": "finished",
+            "root": root,
+            "dry_run": dry_run,
+            "directories": summary.directories,
+            "created": summary.created,
+            "skipped": summary.skipped,
+            "overwritten": summary.overwritten,
+            "backed_up": summary.backed_up,
+            "excluded": summary.excluded,
+        }));
+    }
+}
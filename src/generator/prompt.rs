@@ -0,0 +1,16 @@
+//! Interactive terminal prompts used during generation
+
+use crate::Result;
+use std::io::{self, BufRead, Write};
+
+/// Print a question to stderr and read one trimmed line from stdin
+///
+/// Prompts go to stderr so they never mix with output that may be piped.
+pub fn ask(question: &str) -> Result<String> {
+    eprint!("{} ", question);
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
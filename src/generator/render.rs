@@ -0,0 +1,131 @@
+//! In-memory rendering results
+
+use crate::generator::backend::{MemoryBackend, MemoryEntry};
+use crate::generator::plan::{GenerationPlan, PlannedEntry};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+/// A rendered file: path relative to the project root, contents and mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedFile {
+    pub path: PathBuf,
+    pub contents: Vec<u8>,
+    /// Unix permission bits, when the structure sets them
+    pub mode: Option<u32>,
+}
+
+/// A rendered symlink: path relative to the project root and its target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedSymlink {
+    pub path: PathBuf,
+    pub target: PathBuf,
+}
+
+/// Everything a generation run would write, returned by [`Generator::render`]
+///
+/// Directories, files and symlinks are listed in tree order, with paths relative to
+/// the project root (the root directory itself is not listed).
+///
+/// [`Generator::render`]: crate::Generator::render
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderedProject {
+    pub root: String,
+    pub directories: Vec<PathBuf>,
+    /// Unix permission bits of the directories the structure sets them for
+    pub directory_modes: BTreeMap<PathBuf, u32>,
+    pub files: Vec<RenderedFile>,
+    pub symlinks: Vec<RenderedSymlink>,
+    /// Files the structure gives no content or template: they are created empty,
+    /// and `diff` leaves whatever is in them alone
+    pub without_content: BTreeSet<PathBuf>,
+    /// Final variable values, after defaults and prompting
+    pub variables: BTreeMap<String, String>,
+}
+
+impl RenderedProject {
+    /// Gather the plan's entries back out of the backend they were written to
+    pub(crate) fn collect(plan: &GenerationPlan, backend: &MemoryBackend, variables: &HashMap<String, String>) -> Self {
+        let root = Path::new(&plan.root);
+        let mut rendered = Self {
+            root: plan.root.clone(),
+            variables: variables.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            ..Self::default()
+        };
+
+        for entry in &plan.entries {
+            let path = entry.path().to_path_buf();
+            match (entry, backend.get(&root.join(&path))) {
+                (PlannedEntry::Directory { .. }, Some(MemoryEntry::Directory { mode })) => {
+                    if let Some(mode) = mode {
+                        rendered.directory_modes.insert(path.clone(), mode);
+                    }
+                    rendered.directories.push(path);
+                }
+                (PlannedEntry::File { content, .. }, Some(MemoryEntry::File { contents, mode })) => {
+                    if content.is_none() {
+                        rendered.without_content.insert(path.clone());
+                    }
+                    rendered.files.push(RenderedFile { path, contents, mode });
+                }
+                (PlannedEntry::Symlink { .. }, Some(MemoryEntry::Symlink { target })) => {
+                    rendered.symlinks.push(RenderedSymlink { path, target });
+                }
+                _ => {}
+            }
+        }
+
+        rendered
+    }
+
+    /// Look up a rendered file by its path relative to the project root
+    pub fn file<P: AsRef<Path>>(&self, path: P) -> Option<&RenderedFile> {
+        self.files.iter().find(|file| file.path == path.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::ProjectStructure;
+    use crate::Generator;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn structure() -> ProjectStructure {
+        let mut structure = ProjectStructure::builder("app")
+            .dir("src", |dir| dir.file("main.rs").template("fn main() { println!(\"{{name}}\"); }\n"))
+            .file("README.md").content("# Demo\n")
+            .file(".gitkeep")
+            .build()
+            .unwrap();
+        structure.variables.insert("name".to_string(), "demo".to_string());
+        structure
+    }
+
+    #[test]
+    fn render_writes_nothing() {
+        // `generate` would create `app/` in the working directory
+        assert!(!Path::new("app").exists());
+
+        let rendered = Generator::new().render(&structure()).unwrap();
+
+        assert_eq!(rendered.files.len(), 3);
+        assert!(!Path::new("app").exists());
+    }
+
+    #[test]
+    fn render_matches_what_generate_writes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let structure = structure();
+
+        let rendered = Generator::new().render(&structure).unwrap();
+        Generator::new().generate(&structure, dir.path()).unwrap();
+
+        let root = dir.path().join("app");
+        assert_eq!(rendered.directories, [PathBuf::from("src")]);
+        for file in &rendered.files {
+            assert_eq!(fs::read(root.join(&file.path)).unwrap(), file.contents, "{}", file.path.display());
+        }
+        assert_eq!(rendered.file("src/main.rs").unwrap().contents, b"fn main() { println!(\"demo\"); }\n");
+        assert!(rendered.without_content.iter().eq([Path::new(".gitkeep")]));
+    }
+}
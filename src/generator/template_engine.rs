@@ -1,8 +1,11 @@
 use crate::{Result, ForgeTreeError};
-use handlebars::Handlebars;
+use handlebars::{Context, Handlebars, RenderContext, Renderable, StringOutput, Template};
+use regex::Regex;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::OnceLock;
 
+#[derive(Clone)]
 pub struct TemplateEngine {
     handlebars: Handlebars<'static>,
 }
@@ -20,9 +23,93 @@ impl TemplateEngine {
         Self { handlebars }
     }
 
+    /// Enable or disable handlebars strict mode
+    ///
+    /// In strict mode, referencing a variable that has no value is a render
+    /// error instead of silently producing an empty string.
+    pub fn with_strict_mode(mut self, strict: bool) -> Self {
+        self.handlebars.set_strict_mode(strict);
+        self
+    }
+
+    /// Whether strict mode is enabled
+    pub fn strict_mode(&self) -> bool {
+        self.handlebars.strict_mode()
+    }
+
+    /// Enable or disable HTML escaping of `{{value}}` output (on by default)
+    ///
+    /// Generated files are usually source code rather than HTML, where
+    /// escaping turns `Vec<u8>` into `Vec&lt;u8&gt;`. `{{{value}}}` is never
+    /// escaped either way.
+    pub fn with_html_escape(mut self, escape: bool) -> Self {
+        if escape {
+            self.handlebars.register_escape_fn(handlebars::html_escape);
+        } else {
+            self.handlebars.register_escape_fn(handlebars::no_escape);
+        }
+        self
+    }
+
+    /// Collect the top-level variable names a template refers to
+    ///
+    /// Helper names, literals, `this`/`@`-data and anything inside `each` or
+    /// `with` blocks (which refer to the block's own context) are ignored.
+    pub fn referenced_variables(template: &str) -> BTreeSet<String> {
+        static EXPRESSION: OnceLock<Regex> = OnceLock::new();
+        let expression = EXPRESSION.get_or_init(|| Regex::new(r"\{\{\{?~?([^{}]*?)~?\}?\}\}").unwrap());
+
+        let mut variables = BTreeSet::new();
+        let mut scoped_blocks = 0usize;
+
+        for captures in expression.captures_iter(template) {
+            let inner = captures[1].trim();
+
+            if let Some(closing) = inner.strip_prefix('/') {
+                if matches!(closing.trim(), "each" | "with") {
+                    scoped_blocks = scoped_blocks.saturating_sub(1);
+                }
+                continue;
+            }
+            if inner.is_empty() || inner.starts_with('!') || inner.starts_with('>') || inner == "else" {
+                continue;
+            }
+
+            let (is_block, body) = match inner.strip_prefix(['#', '^']) {
+                Some(body) => (true, body),
+                None => (false, inner),
+            };
+            let mut tokens = body.split_whitespace();
+            let first = tokens.next().unwrap_or_default();
+            let arguments: Vec<&str> = tokens.collect();
+
+            if scoped_blocks == 0 {
+                // A lone token is a variable; otherwise the first token is a helper
+                let candidates: Vec<&str> = if arguments.is_empty() && !is_block {
+                    vec![first]
+                } else {
+                    arguments.iter()
+                        .map(|arg| arg.split_once('=').map_or(*arg, |(_, value)| value))
+                        .collect()
+                };
+                for candidate in candidates {
+                    if let Some(name) = variable_root(candidate) {
+                        variables.insert(name);
+                    }
+                }
+            }
+
+            if is_block && matches!(first, "each" | "with") {
+                scoped_blocks += 1;
+            }
+        }
+
+        variables
+    }
+
     pub fn render_template(&self, template: &str, variables: &HashMap<String, String>) -> Result<String> {
         let json_vars: Value = serde_json::to_value(variables)
-            .map_err(|e| ForgeTreeError::Parse(format!("Failed to serialize variables: {}", e)))?;
+            .map_err(|e| ForgeTreeError::parse(format!("Failed to serialize variables: {}", e)))?;
         
         // Remove the explicit map_err - the #[from] conversion handles it automatically
         self.handlebars
@@ -30,12 +117,47 @@ impl TemplateEngine {
             .map_err(ForgeTreeError::TemplateRender)
     }
 
+    /// Render a template against an already-built JSON context
+    pub fn render_with_context(&self, template: &str, context: &Value) -> Result<String> {
+        self.handlebars
+            .render_template(template, context)
+            .map_err(ForgeTreeError::TemplateRender)
+    }
+
+    /// Render a template inside a loop iteration, exposing the position as `{{@index}}`
+    pub fn render_with_index(&self, template: &str, context: &Value, index: usize) -> Result<String> {
+        let template = Template::compile(template).map_err(|e| ForgeTreeError::TemplateParse(Box::new(e)))?;
+        let context = Context::wraps(context)?;
+
+        let mut render_context = RenderContext::new(None);
+        if let Some(block) = render_context.block_mut() {
+            block.set_local_var("index", Value::from(index));
+        }
+
+        let mut output = StringOutput::new();
+        template.render(&self.handlebars, &context, &mut render_context, &mut output)?;
+        output.into_string()
+            .map_err(|e| ForgeTreeError::parse(format!("Rendered template is not valid UTF-8: {}", e)))
+    }
+
     pub fn register_template(&mut self, name: &str, template: &str) -> Result<()> {
         // This now uses the TemplateParse variant for registration errors
         self.handlebars
             .register_template_string(name, template)
-            .map_err(ForgeTreeError::TemplateParse)
+            .map_err(|e| ForgeTreeError::TemplateParse(Box::new(e)))
+    }
+}
+
+/// Return the top-level variable of a handlebars path expression, if it is one
+fn variable_root(token: &str) -> Option<String> {
+    let root = token.split(['.', '/']).next().unwrap_or_default();
+    let is_identifier = root.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && root.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+
+    if !is_identifier || matches!(root, "this" | "true" | "false" | "null" | "undefined") {
+        return None;
     }
+    Some(root.to_string())
 }
 
 // Helper functions remain the same...
@@ -120,3 +242,29 @@ impl Default for TemplateEngine {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(engine: &TemplateEngine, value: &str) -> String {
+        let variables = HashMap::from([("type".to_string(), value.to_string())]);
+        engine.render_template("let x: {{type}};", &variables).unwrap()
+    }
+
+    #[test]
+    fn escapes_html_by_default() {
+        assert_eq!(render(&TemplateEngine::new(), "Vec<u8>"), "let x: Vec&lt;u8&gt;;");
+        assert_eq!(render(&TemplateEngine::new(), "a&b"), "let x: a&amp;b;");
+    }
+
+    #[test]
+    fn writes_values_as_given_without_html_escape() {
+        let engine = TemplateEngine::new().with_html_escape(false);
+        assert_eq!(render(&engine, "Vec<u8>"), "let x: Vec<u8>;");
+        assert_eq!(render(&engine, "a&b"), "let x: a&b;");
+
+        let engine = engine.with_html_escape(true);
+        assert_eq!(render(&engine, "a&b"), "let x: a&amp;b;");
+    }
+}
@@ -0,0 +1,301 @@
+//! Turning a forged project into a git repository
+//!
+//! With `--git`, the project root is initialised as a repository (unless it
+//! is already inside one), gets a `.gitignore` and, optionally, an initial
+//! commit. Structure files provide the defaults in their front matter:
+//!
+//! ```yaml
+//! git:
+//!   ignore:
+//!     - target/
+//!     - "*.log"
+//!   message: "Initial commit of {{project_name}}"
+//!   author: "{{author}} <{{email}}>"
+//! ```
+//!
+//! Everything runs through the local `git` binary and never touches the
+//! network.
+
+use crate::generator::{ProgressReporter, TemplateEngine};
+use crate::{ForgeTreeError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Name of the ignore file written into the project root
+pub const GITIGNORE_NAME: &str = ".gitignore";
+
+/// Commit message used when neither the structure nor the caller sets one
+pub const DEFAULT_COMMIT_MESSAGE: &str = "Initial commit";
+
+/// Git defaults declared by a structure
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GitSettings {
+    /// Patterns written to `.gitignore`, rendered with the template variables
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+    /// Template for the initial commit message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Template for the initial commit's author, as `Name <email>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+}
+
+impl GitSettings {
+    pub fn is_empty(&self) -> bool {
+        self.ignore.is_empty() && self.message.is_none() && self.author.is_none()
+    }
+
+    /// Add another structure's settings; its patterns come after these and its message and author win
+    pub fn extend(&mut self, other: GitSettings) {
+        for pattern in other.ignore {
+            if !self.ignore.contains(&pattern) {
+                self.ignore.push(pattern);
+            }
+        }
+        if other.message.is_some() {
+            self.message = other.message;
+        }
+        if other.author.is_some() {
+            self.author = other.author;
+        }
+    }
+}
+
+/// What the generator does with git after writing a project
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitOptions {
+    /// Create an initial commit of everything generated
+    pub commit: bool,
+    /// Commit message template, overriding the structure's
+    pub message: Option<String>,
+    /// Author template (`Name <email>`), overriding the structure's
+    pub author: Option<String>,
+}
+
+impl GitOptions {
+    /// Initialise a repository and write `.gitignore`, without committing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_commit(mut self, commit: bool) -> Self {
+        self.commit = commit;
+        self
+    }
+
+    pub fn with_message(mut self, message: &str) -> Self {
+        self.message = Some(message.to_string());
+        self
+    }
+
+    pub fn with_author(mut self, author: &str) -> Self {
+        self.author = Some(author.to_string());
+        self
+    }
+}
+
+/// Contents of `.gitignore` for the given patterns, or `None` if there are none
+pub fn gitignore_content(patterns: &[String]) -> Option<String> {
+    if patterns.is_empty() {
+        return None;
+    }
+    Some(patterns.iter().map(|pattern| format!("{}\n", pattern)).collect())
+}
+
+/// Split `Name <email>` into its parts
+pub fn parse_author(author: &str) -> Result<(String, String)> {
+    let invalid = || ForgeTreeError::Git(format!("author `{}` should look like `Name <email>`", author));
+    let (name, rest) = author.split_once('<').ok_or_else(invalid)?;
+    let email = rest.trim().strip_suffix('>').ok_or_else(invalid)?;
+    let name = name.trim();
+    if name.is_empty() || email.trim().is_empty() {
+        return Err(invalid());
+    }
+    Ok((name.to_string(), email.trim().to_string()))
+}
+
+/// Runs git in a project directory
+pub(crate) struct GitRunner<'a> {
+    pub(crate) template_engine: &'a TemplateEngine,
+    pub(crate) reporter: &'a dyn ProgressReporter,
+    /// Template context used to render the message and author
+    pub(crate) context: &'a Value,
+    pub(crate) dry_run: bool,
+}
+
+impl GitRunner<'_> {
+    /// Whether `dir`, or the closest ancestor that exists so far, is inside a work tree
+    pub(crate) fn inside_repository(&self, dir: &Path) -> bool {
+        let Some(dir) = dir.ancestors().find(|dir| dir.is_dir()) else {
+            return false;
+        };
+        Command::new("git")
+            .args(["rev-parse", "--is-inside-work-tree"])
+            .current_dir(dir)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    pub(crate) fn init(&self, dir: &Path) -> Result<()> {
+        self.git(dir, &["init", "--quiet"], &[])
+    }
+
+    /// Stage everything in `dir` and commit it
+    pub(crate) fn commit(&self, dir: &Path, options: &GitOptions, settings: &GitSettings) -> Result<()> {
+        let message = options.message.as_ref().or(settings.message.as_ref());
+        let message = match message {
+            Some(message) => self.template_engine.render_with_context(message, self.context)?,
+            None => DEFAULT_COMMIT_MESSAGE.to_string(),
+        };
+        let author = match options.author.as_ref().or(settings.author.as_ref()) {
+            Some(author) => Some(parse_author(&self.template_engine.render_with_context(author, self.context)?)?),
+            None => None,
+        };
+
+        // A new project's first commit is made by its author, whatever git is configured with
+        let mut env = Vec::new();
+        if let Some((name, email)) = &author {
+            for (variable, value) in [
+                ("GIT_AUTHOR_NAME", name),
+                ("GIT_AUTHOR_EMAIL", email),
+                ("GIT_COMMITTER_NAME", name),
+                ("GIT_COMMITTER_EMAIL", email),
+            ] {
+                env.push((variable, value.as_str()));
+            }
+        }
+
+        self.git(dir, &["add", "--all"], &[])?;
+        self.git(dir, &["commit", "--quiet", "--message", &message], &env)
+    }
+
+    /// Run one git command, reporting it first; in a dry run it is only reported
+    fn git(&self, dir: &Path, args: &[&str], env: &[(&str, &str)]) -> Result<()> {
+        let command_line = format!("git {}", args.join(" "));
+        self.reporter.git_started(&command_line);
+        if self.dry_run {
+            return Ok(());
+        }
+
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .envs(env.iter().copied())
+            .stdin(Stdio::null())
+            .stdout(Stdio::from(std::io::stderr()))
+            .status()
+            .map_err(|e| ForgeTreeError::Git(format!("`{}` could not be started: {}", command_line, e)))?;
+
+        if !status.success() {
+            return Err(ForgeTreeError::Git(format!("`{}` failed ({})", command_line, status)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder {
+        commands: Mutex<Vec<String>>,
+    }
+
+    impl ProgressReporter for Recorder {
+        fn git_started(&self, command: &str) {
+            self.commands.lock().unwrap().push(command.to_string());
+        }
+    }
+
+    fn git_output(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git").args(args).current_dir(dir).output().unwrap();
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    }
+
+    #[test]
+    fn writes_one_pattern_per_line() {
+        assert_eq!(gitignore_content(&[]), None);
+        assert_eq!(gitignore_content(&["target/".into(), "*.log".into()]).as_deref(), Some("target/\n*.log\n"));
+    }
+
+    #[test]
+    fn parses_authors() {
+        assert_eq!(parse_author(" Ada Lovelace <ada@example.com> ").unwrap(), ("Ada Lovelace".into(), "ada@example.com".into()));
+        for invalid in ["Ada", "<ada@example.com>", "Ada <>", "Ada <ada@example.com"] {
+            let error = parse_author(invalid).unwrap_err();
+            assert_eq!(error.exit_code(), 9, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn later_settings_add_patterns_and_override_the_rest() {
+        let mut settings = GitSettings { ignore: vec!["target/".into()], message: Some("base".into()), author: None };
+        settings.extend(GitSettings { ignore: vec!["target/".into(), "*.log".into()], message: Some("child".into()), author: None });
+
+        assert_eq!(settings.ignore, ["target/", "*.log"]);
+        assert_eq!(settings.message.as_deref(), Some("child"));
+        assert!(serde_yaml::from_str::<GitSettings>("ignored: [x]").is_err());
+    }
+
+    #[test]
+    fn initialises_and_commits_with_the_rendered_author() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let engine = TemplateEngine::new();
+        let context = json!({ "name": "demo", "author": "Ada" });
+        let reporter = Recorder::default();
+        let runner = GitRunner { template_engine: &engine, reporter: &reporter, context: &context, dry_run: false };
+        let project = dir.path().join("demo");
+        std::fs::create_dir(&project).unwrap();
+        std::fs::write(project.join("README.md"), "# demo\n").unwrap();
+
+        runner.init(&project).unwrap();
+        assert!(runner.inside_repository(&project.join("src/not-yet-created")));
+        let options = GitOptions::new().with_commit(true).with_message("Start {{name}}").with_author("{{author}} <ada@example.com>");
+        runner.commit(&project, &options, &GitSettings::default()).unwrap();
+
+        assert_eq!(git_output(&project, &["log", "--format=%an <%ae>|%cn|%s"]), "Ada <ada@example.com>|Ada|Start demo");
+        assert_eq!(git_output(&project, &["ls-files"]), "README.md");
+        assert_eq!(
+            *reporter.commands.lock().unwrap(),
+            ["git init --quiet", "git add --all", "git commit --quiet --message Start demo"]
+        );
+    }
+
+    #[test]
+    fn dry_runs_only_report_commands() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let engine = TemplateEngine::new();
+        let context = json!({});
+        let reporter = Recorder::default();
+        let runner = GitRunner { template_engine: &engine, reporter: &reporter, context: &context, dry_run: true };
+
+        runner.init(dir.path()).unwrap();
+        runner.commit(dir.path(), &GitOptions::new().with_commit(true), &GitSettings::default()).unwrap();
+
+        assert!(!dir.path().join(".git").exists());
+        assert_eq!(reporter.commands.lock().unwrap().last().unwrap(), "git commit --quiet --message Initial commit");
+    }
+
+    #[test]
+    fn invalid_authors_fail_before_anything_is_committed() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let engine = TemplateEngine::new();
+        let context = json!({});
+        let reporter = Recorder::default();
+        let runner = GitRunner { template_engine: &engine, reporter: &reporter, context: &context, dry_run: false };
+
+        let error = runner.commit(dir.path(), &GitOptions::new().with_author("nobody"), &GitSettings::default()).unwrap_err();
+
+        assert!(matches!(error, ForgeTreeError::Git(_)), "{}", error);
+        assert!(reporter.commands.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,419 @@
+//! Commands run before and after generation
+//!
+//! Structure files declare hooks in their front matter:
+//!
+//! ```yaml
+//! hooks:
+//!   pre_generate:
+//!     - cargo --version
+//!   post_generate:
+//!     - git init
+//!     - run: cargo fmt
+//!       on_failure: warn
+//!     - script: scripts/setup.sh
+//! ```
+//!
+//! Commands run through the shell in the project root exactly as written.
+//! They are not rendered as templates, so variable values never become part
+//! of a command line; every variable is exported as `FORGE_TREE_VAR_<NAME>`
+//! instead (`echo "$FORGE_TREE_VAR_NAME"`). Scripts are resolved relative to
+//! the structure file that declares them and run without going through the
+//! shell's parser. Hook output goes to stderr so stdout stays usable for
+//! reports.
+
+use crate::generator::ProgressReporter;
+use crate::{ForgeTreeError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Prefix of the environment variables holding template variables
+pub const VARIABLE_ENV_PREFIX: &str = "FORGE_TREE_VAR_";
+
+/// When a hook runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookStage {
+    /// After planning, before anything is written
+    PreGenerate,
+    /// After every file has been written
+    PostGenerate,
+}
+
+impl fmt::Display for HookStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::PreGenerate => write!(f, "pre_generate"),
+            Self::PostGenerate => write!(f, "post_generate"),
+        }
+    }
+}
+
+/// What a failing hook does to the run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailurePolicy {
+    /// Stop with an error
+    #[default]
+    Abort,
+    /// Report a warning and carry on
+    Warn,
+    /// Carry on silently
+    Ignore,
+}
+
+impl FailurePolicy {
+    fn is_abort(&self) -> bool {
+        *self == Self::Abort
+    }
+}
+
+impl fmt::Display for FailurePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Abort => write!(f, "abort"),
+            Self::Warn => write!(f, "warn"),
+            Self::Ignore => write!(f, "ignore"),
+        }
+    }
+}
+
+/// A single command or script
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "HookSpec", into = "HookSpec")]
+pub struct Hook {
+    pub action: HookAction,
+    pub on_failure: FailurePolicy,
+    /// Structure file that declared the hook, if it came from a file
+    pub source: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookAction {
+    /// Shell command line, run as written
+    Run(String),
+    /// Script file run with the shell, relative to the declaring file
+    Script(PathBuf),
+}
+
+/// How a hook is written: a bare command, or a map with options
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum HookSpec {
+    Command(String),
+    Detailed {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        run: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        script: Option<PathBuf>,
+        #[serde(default, skip_serializing_if = "FailurePolicy::is_abort")]
+        on_failure: FailurePolicy,
+    },
+}
+
+impl TryFrom<HookSpec> for Hook {
+    type Error = String;
+
+    fn try_from(spec: HookSpec) -> std::result::Result<Self, String> {
+        let (action, on_failure) = match spec {
+            HookSpec::Command(command) => (HookAction::Run(command), FailurePolicy::default()),
+            HookSpec::Detailed { run: Some(command), script: None, on_failure } => (HookAction::Run(command), on_failure),
+            HookSpec::Detailed { run: None, script: Some(script), on_failure } => (HookAction::Script(script), on_failure),
+            HookSpec::Detailed { .. } => return Err("a hook needs exactly one of `run` or `script`".to_string()),
+        };
+        Ok(Self { action, on_failure, source: None })
+    }
+}
+
+impl From<Hook> for HookSpec {
+    fn from(hook: Hook) -> Self {
+        match (hook.action, hook.on_failure) {
+            (HookAction::Run(command), FailurePolicy::Abort) => HookSpec::Command(command),
+            (HookAction::Run(command), on_failure) => HookSpec::Detailed { run: Some(command), script: None, on_failure },
+            (HookAction::Script(script), on_failure) => HookSpec::Detailed { run: None, script: Some(script), on_failure },
+        }
+    }
+}
+
+impl Hook {
+    /// A command hook that aborts generation when it fails
+    pub fn run(command: &str) -> Self {
+        Self { action: HookAction::Run(command.to_string()), on_failure: FailurePolicy::default(), source: None }
+    }
+
+    pub fn with_on_failure(mut self, on_failure: FailurePolicy) -> Self {
+        self.on_failure = on_failure;
+        self
+    }
+
+    /// Whether the hook may run without asking
+    ///
+    /// Hooks declared in code, or by a file inside one of the `trusted`
+    /// directories, are trusted.
+    pub fn is_trusted(&self, trusted: &[PathBuf]) -> bool {
+        let Some(source) = &self.source else {
+            return true;
+        };
+        let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let source = canonical(source);
+        trusted.iter().any(|dir| source.starts_with(canonical(dir)))
+    }
+
+    /// What this hook runs, for showing before it runs
+    pub fn command_line(&self) -> String {
+        match &self.action {
+            HookAction::Run(command) => command.clone(),
+            HookAction::Script(script) if cfg!(windows) => self.script_path(script).display().to_string(),
+            HookAction::Script(script) => format!("sh {}", self.script_path(script).display()),
+        }
+    }
+
+    /// A script's location, relative to the file that declared it
+    fn script_path(&self, script: &Path) -> PathBuf {
+        let base = self.source.as_ref().and_then(|source| source.parent()).unwrap_or(Path::new(""));
+        let script = base.join(script);
+        script.canonicalize().unwrap_or(script)
+    }
+}
+
+impl fmt::Display for Hook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.action {
+            HookAction::Run(command) => f.write_str(command),
+            HookAction::Script(script) => write!(f, "script {}", script.display()),
+        }
+    }
+}
+
+/// The hooks of a structure, by stage
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hooks {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_generate: Vec<Hook>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_generate: Vec<Hook>,
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        self.pre_generate.is_empty() && self.post_generate.is_empty()
+    }
+
+    pub fn stage(&self, stage: HookStage) -> &[Hook] {
+        match stage {
+            HookStage::PreGenerate => &self.pre_generate,
+            HookStage::PostGenerate => &self.post_generate,
+        }
+    }
+
+    /// Every hook, pre-generate ones first
+    pub fn iter(&self) -> impl Iterator<Item = &Hook> {
+        self.pre_generate.iter().chain(&self.post_generate)
+    }
+
+    /// Append another set of hooks after these, stage by stage
+    pub fn extend(&mut self, other: Hooks) {
+        self.pre_generate.extend(other.pre_generate);
+        self.post_generate.extend(other.post_generate);
+    }
+
+    /// Record the file these hooks were declared in
+    pub fn set_source(&mut self, source: &Path) {
+        for hook in self.pre_generate.iter_mut().chain(&mut self.post_generate) {
+            hook.source = Some(source.to_path_buf());
+        }
+    }
+
+    /// Files declaring hooks outside the trusted directories, without repeats
+    pub fn untrusted_sources(&self, trusted: &[PathBuf]) -> Vec<&Path> {
+        let mut sources: Vec<&Path> = Vec::new();
+        for hook in self.iter().filter(|hook| !hook.is_trusted(trusted)) {
+            if let Some(source) = hook.source.as_deref().filter(|source| !sources.contains(source)) {
+                sources.push(source);
+            }
+        }
+        sources
+    }
+}
+
+/// Runs the hooks of one stage in a project directory
+pub(crate) struct HookRunner<'a> {
+    pub(crate) reporter: &'a dyn ProgressReporter,
+    pub(crate) variables: &'a HashMap<String, String>,
+    pub(crate) dry_run: bool,
+}
+
+impl HookRunner<'_> {
+    /// Run `hooks` in order in `dir`; in a dry run they are only reported
+    pub(crate) fn run(&self, stage: HookStage, hooks: &[Hook], dir: &Path) -> Result<()> {
+        for hook in hooks {
+            let command = hook.command_line();
+            self.reporter.hook_started(stage, &command);
+            if self.dry_run {
+                continue;
+            }
+
+            let mut status = None;
+            self.reporter.suspend(&mut || status = Some(self.command(hook, dir).status()));
+            let status = status.expect("reporters run the suspended closure").map_err(|e| {
+                ForgeTreeError::Hook(format!("{} hook `{}` could not be started: {}", stage, command, e))
+            })?;
+
+            if status.success() {
+                continue;
+            }
+            let message = format!("{} hook `{}` failed ({})", stage, command, status);
+            match hook.on_failure {
+                FailurePolicy::Abort => return Err(ForgeTreeError::Hook(message)),
+                FailurePolicy::Warn => self.reporter.warning(&message),
+                FailurePolicy::Ignore => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn command(&self, hook: &Hook, dir: &Path) -> Command {
+        let mut command = match &hook.action {
+            HookAction::Run(command_line) if cfg!(windows) => {
+                let mut command = Command::new("cmd");
+                command.arg("/C").arg(command_line);
+                command
+            }
+            HookAction::Run(command_line) => {
+                let mut command = Command::new("sh");
+                command.arg("-c").arg(command_line);
+                command
+            }
+            HookAction::Script(script) if cfg!(windows) => Command::new(hook.script_path(script)),
+            HookAction::Script(script) => {
+                let mut command = Command::new("sh");
+                command.arg(hook.script_path(script));
+                command
+            }
+        };
+
+        command.current_dir(dir)
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::from(std::io::stderr()))
+            .env("FORGE_TREE_ROOT", dir);
+        for (name, value) in self.variables {
+            command.env(env_name(name), value);
+        }
+        command
+    }
+}
+
+/// Environment variable name for a template variable: `project-name` → `FORGE_TREE_VAR_PROJECT_NAME`
+pub fn env_name(variable: &str) -> String {
+    let name: String = variable.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    format!("{}{}", VARIABLE_ENV_PREFIX, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Keeps the commands and warnings it is told about
+    #[derive(Default)]
+    struct Recorder {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl ProgressReporter for Recorder {
+        fn hook_started(&self, stage: HookStage, command: &str) {
+            self.events.lock().unwrap().push(format!("{}: {}", stage, command));
+        }
+
+        fn warning(&self, message: &str) {
+            self.events.lock().unwrap().push(format!("warning: {}", message));
+        }
+    }
+
+    fn run(hooks: &[Hook], dry_run: bool) -> (Result<()>, Vec<String>, tempfile::TempDir) {
+        let dir = tempfile::TempDir::new().unwrap();
+        let reporter = Recorder::default();
+        let variables = HashMap::from([("name".to_string(), "demo".to_string())]);
+        let runner = HookRunner { reporter: &reporter, variables: &variables, dry_run };
+
+        let result = runner.run(HookStage::PostGenerate, hooks, dir.path());
+        (result, reporter.events.into_inner().unwrap(), dir)
+    }
+
+    #[test]
+    fn reads_commands_and_detailed_hooks() {
+        let hooks: Hooks = serde_yaml::from_str(
+            "pre_generate:\n  - make\npost_generate:\n  - run: cargo fmt\n    on_failure: warn\n  - script: setup.sh\n"
+        ).unwrap();
+
+        assert_eq!(hooks.pre_generate, [Hook::run("make")]);
+        assert_eq!(hooks.post_generate[0], Hook::run("cargo fmt").with_on_failure(FailurePolicy::Warn));
+        assert_eq!(hooks.post_generate[1].action, HookAction::Script("setup.sh".into()));
+        assert_eq!(serde_yaml::to_string(&hooks).unwrap().lines().nth(1), Some("- make"));
+
+        assert!(serde_yaml::from_str::<Hooks>("post_generate:\n  - run: a\n    script: b\n").is_err());
+        assert!(serde_yaml::from_str::<Hooks>("on_generate:\n  - make\n").is_err());
+    }
+
+    #[test]
+    fn names_variables_for_the_environment() {
+        assert_eq!(env_name("project-name"), "FORGE_TREE_VAR_PROJECT_NAME");
+        assert_eq!(env_name("port"), "FORGE_TREE_VAR_PORT");
+    }
+
+    #[test]
+    fn trusts_hooks_from_code_and_trusted_directories() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut hooks = Hooks { post_generate: vec![Hook::run("make")], ..Hooks::default() };
+        assert!(hooks.untrusted_sources(&[]).is_empty());
+
+        hooks.set_source(&dir.path().join("app.tree"));
+        assert_eq!(hooks.untrusted_sources(&[]), [dir.path().join("app.tree")]);
+        assert!(hooks.untrusted_sources(&[dir.path().to_path_buf()]).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_hooks_abort_by_default() {
+        let hooks = [Hook::run("exit 3"), Hook::run("touch after")];
+
+        let (result, events, dir) = run(&hooks, false);
+
+        let error = result.unwrap_err();
+        assert!(matches!(error, ForgeTreeError::Hook(_)), "{}", error);
+        assert_eq!(error.exit_code(), 8);
+        assert!(error.to_string().contains("`exit 3` failed"), "{}", error);
+        assert_eq!(events, ["post_generate: exit 3"]);
+        assert!(!dir.path().join("after").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn warn_and_ignore_keep_going() {
+        let hooks = [
+            Hook::run("exit 1").with_on_failure(FailurePolicy::Warn),
+            Hook::run("exit 2").with_on_failure(FailurePolicy::Ignore),
+            Hook::run("printf '%s' \"$FORGE_TREE_VAR_NAME\" > after"),
+        ];
+
+        let (result, events, dir) = run(&hooks, false);
+
+        result.unwrap();
+        assert_eq!(events.len(), 4, "{:?}", events);
+        assert!(events[1].starts_with("warning: post_generate hook `exit 1` failed"), "{:?}", events);
+        assert_eq!(std::fs::read_to_string(dir.path().join("after")).unwrap(), "demo");
+    }
+
+    #[test]
+    fn dry_runs_only_report_hooks() {
+        let (result, events, dir) = run(&[Hook::run("touch ran")], true);
+
+        result.unwrap();
+        assert_eq!(events, ["post_generate: touch ran"]);
+        assert!(!dir.path().join("ran").exists());
+    }
+}
@@ -22,9 +22,18 @@
 
 pub mod cli;
 pub mod config;
+pub mod diff;
 pub mod error;
 pub mod generator;
+pub mod git;
+pub mod hooks;
+pub mod lockfile;
 pub mod parser;
+pub mod report;
+pub mod scanner;
+pub mod undo;
+pub mod update;
+pub mod variables;
 
 pub use error::{Result, ForgeTreeError};
 pub use generator::Generator;
@@ -0,0 +1,392 @@
+//! Generation manifest (`.forge-tree.lock`)
+//!
+//! Records what a forge run produced so later commands can tell which files
+//! came from forge-tree and whether they were modified since.
+
+use crate::generator::{FileOutcome, GenerationPlan, GenerationSummary, PlannedEntry};
+use crate::{ForgeTreeError, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// File name of the manifest, written into the project root
+pub const LOCKFILE_NAME: &str = ".forge-tree.lock";
+
+/// Version of the manifest format
+pub const LOCKFILE_VERSION: u32 = 1;
+
+/// The structure file a project was forged from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockSource {
+    pub path: String,
+    /// `sha256:`-prefixed hash of the file's contents
+    pub hash: String,
+}
+
+impl LockSource {
+    /// Describe a structure file, hashing its current contents
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        Ok(Self {
+            path: canonical.display().to_string(),
+            hash: hash_bytes(&fs::read(path)?),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockEntryKind {
+    Directory,
+    File,
+    Symlink,
+}
+
+/// What forge did to an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockEntryStatus {
+    /// Did not exist before and was created
+    Created,
+    /// A directory that already existed
+    Existed,
+    /// A file that existed and was replaced
+    Overwritten,
+    /// A file that existed, was moved to `backup` and replaced
+    BackedUp,
+    /// A file that existed and was left untouched
+    Skipped,
+}
+
+/// One generated directory or file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockEntry {
+    /// Path relative to the project root, with `/` separators
+    pub path: String,
+    pub kind: LockEntryKind,
+    pub status: LockEntryStatus,
+    /// Hash of the contents forge wrote (files only, not for skipped files)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    /// Where the previous file was moved to, relative to the project root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup: Option<String>,
+    /// The rendered contents, kept as the merge base for `update`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Where a symlink points, as written into the link
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+impl LockEntry {
+    pub fn directory(path: String, status: LockEntryStatus) -> Self {
+        Self { path, kind: LockEntryKind::Directory, status, hash: None, backup: None, content: None, target: None }
+    }
+
+    /// A file entry; the hash and contents are only kept if forge wrote the file,
+    /// and the contents only if they are text
+    pub fn file(path: String, status: LockEntryStatus, content: &[u8]) -> Self {
+        let written = status != LockEntryStatus::Skipped;
+        Self {
+            path,
+            kind: LockEntryKind::File,
+            status,
+            hash: written.then(|| hash_bytes(content)),
+            backup: None,
+            content: written.then(|| std::str::from_utf8(content).ok().map(str::to_string)).flatten(),
+            target: None,
+        }
+    }
+
+    /// A symlink entry; the target is only kept if forge created the link
+    pub fn symlink(path: String, status: LockEntryStatus, target: &Path) -> Self {
+        let written = status != LockEntryStatus::Skipped;
+        Self {
+            path,
+            kind: LockEntryKind::Symlink,
+            status,
+            hash: None,
+            backup: None,
+            content: None,
+            target: written.then(|| target.to_string_lossy().into_owned()),
+        }
+    }
+}
+
+/// Contents of `.forge-tree.lock`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub version: u32,
+    /// forge-tree version that wrote the manifest
+    pub tool_version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<LockSource>,
+    /// Final variable values, after defaults and prompting
+    pub variables: BTreeMap<String, String>,
+    /// Rendered name of the project root directory
+    pub root: String,
+    /// Whether the project root itself was created by the run
+    pub root_created: bool,
+    /// Generated entries in tree order
+    pub entries: Vec<LockEntry>,
+}
+
+impl Lockfile {
+    /// Build the manifest for a finished generation run
+    ///
+    /// `new_directories` are the directories (relative to the output root,
+    /// like the summary's file paths) that did not exist before the run.
+    pub fn new(
+        plan: &GenerationPlan,
+        summary: &GenerationSummary,
+        variables: &HashMap<String, String>,
+        source: Option<LockSource>,
+    ) -> Self {
+        let root = Path::new(&plan.root);
+        let new_directories: HashSet<&Path> = summary.new_directories.iter().map(PathBuf::as_path).collect();
+        let outcomes: HashMap<&Path, &FileOutcome> = summary.files.iter()
+            .map(|(path, outcome)| (path.as_path(), outcome))
+            .collect();
+
+        let mut entries = Vec::new();
+        for entry in &plan.entries {
+            let full_path = root.join(entry.path());
+            let path = slash_path(entry.path());
+
+            match entry {
+                PlannedEntry::Excluded { .. } => {}
+                PlannedEntry::Directory { .. } => {
+                    let status = if new_directories.contains(full_path.as_path()) {
+                        LockEntryStatus::Created
+                    } else {
+                        LockEntryStatus::Existed
+                    };
+                    entries.push(LockEntry::directory(path, status));
+                }
+                PlannedEntry::File { content, .. } => {
+                    let Some(outcome) = outcomes.get(full_path.as_path()) else {
+                        continue;
+                    };
+                    let (status, backup) = file_status(outcome, root);
+                    let mut entry = LockEntry::file(path, status, content.as_deref().unwrap_or_default().as_bytes());
+                    entry.backup = backup;
+                    entries.push(entry);
+                }
+                PlannedEntry::Symlink { target, .. } => {
+                    let Some(outcome) = outcomes.get(full_path.as_path()) else {
+                        continue;
+                    };
+                    let (status, backup) = file_status(outcome, root);
+                    let mut entry = LockEntry::symlink(path, status, target);
+                    entry.backup = backup;
+                    entries.push(entry);
+                }
+            }
+        }
+
+        Self {
+            version: LOCKFILE_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            source,
+            variables: variables.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            root: plan.root.clone(),
+            root_created: new_directories.contains(root),
+            entries,
+        }
+    }
+
+    /// Read the manifest from a project root directory
+    pub fn load<P: AsRef<Path>>(project_root: P) -> Result<Self> {
+        let path = project_root.as_ref().join(LOCKFILE_NAME);
+        let text = fs::read_to_string(&path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => ForgeTreeError::InvalidPath(format!(
+                "No {} found in {}", LOCKFILE_NAME, project_root.as_ref().display()
+            )),
+            _ => ForgeTreeError::Io(e),
+        })?;
+        Self::from_json(&text)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self> {
+        let lockfile: Self = serde_json::from_str(text)
+            .map_err(|e| ForgeTreeError::parse(format!("Invalid {}: {}", LOCKFILE_NAME, e)))?;
+        if lockfile.version != LOCKFILE_VERSION {
+            return Err(ForgeTreeError::parse(format!(
+                "Unsupported {} version {} (expected {})", LOCKFILE_NAME, lockfile.version, LOCKFILE_VERSION
+            )));
+        }
+        // Undo and update delete and write these paths, so they must stay inside the project
+        let paths = lockfile.entries.iter().flat_map(|entry| [Some(&entry.path), entry.backup.as_ref()]).flatten();
+        for path in paths {
+            if !is_inside_project(path) {
+                return Err(ForgeTreeError::parse(format!(
+                    "Invalid {}: '{}' is not a path inside the project", LOCKFILE_NAME, path
+                )));
+            }
+        }
+        Ok(lockfile)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        let mut json = serde_json::to_string_pretty(self)
+            .map_err(|e| ForgeTreeError::Io(std::io::Error::other(e)))?;
+        json.push('\n');
+        Ok(json)
+    }
+
+    /// Write the manifest into a project root directory
+    pub fn save<P: AsRef<Path>>(&self, project_root: P) -> Result<()> {
+        fs::write(project_root.as_ref().join(LOCKFILE_NAME), self.to_json()?)?;
+        Ok(())
+    }
+
+    /// Look up an entry by its path relative to the project root
+    pub fn entry(&self, path: &str) -> Option<&LockEntry> {
+        self.entries.iter().find(|entry| entry.path == path)
+    }
+}
+
+/// Manifest status and backup location for what happened to a file or symlink
+fn file_status(outcome: &FileOutcome, root: &Path) -> (LockEntryStatus, Option<String>) {
+    match outcome {
+        FileOutcome::Created => (LockEntryStatus::Created, None),
+        FileOutcome::Overwritten => (LockEntryStatus::Overwritten, None),
+        FileOutcome::Skipped => (LockEntryStatus::Skipped, None),
+        FileOutcome::BackedUp(backup) => {
+            let backup = backup.strip_prefix(root).unwrap_or(backup);
+            (LockEntryStatus::BackedUp, Some(slash_path(backup)))
+        }
+    }
+}
+
+/// `sha256:`-prefixed hex digest of some bytes
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    format!("sha256:{:x}", Sha256::digest(bytes))
+}
+
+/// `/`-separated form of a relative path
+pub(crate) fn slash_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Location of a `/`-separated relative path inside `project_root`
+///
+/// Fails for absolute paths and paths with `.` or `..` components, which
+/// could point outside the project.
+pub(crate) fn project_path(project_root: &Path, path: &str) -> Result<PathBuf> {
+    if !is_inside_project(path) {
+        return Err(ForgeTreeError::InvalidPath(format!(
+            "'{}' is not a path inside {}", path, project_root.display()
+        )));
+    }
+    Ok(project_root.join(native_path(path)))
+}
+
+fn is_inside_project(path: &str) -> bool {
+    // Check the path as written; splitting it on `/` first would drop a leading root
+    let path = Path::new(path);
+    path.components().next().is_some() && path.components().all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// Platform form of a `/`-separated manifest path
+pub(crate) fn native_path(path: &str) -> PathBuf {
+    path.split('/').collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lockfile() -> Lockfile {
+        Lockfile {
+            version: LOCKFILE_VERSION,
+            tool_version: "0.0.0".to_string(),
+            source: None,
+            variables: BTreeMap::from([("name".to_string(), "demo".to_string())]),
+            root: "app".to_string(),
+            root_created: true,
+            entries: vec![
+                LockEntry::directory("src".to_string(), LockEntryStatus::Created),
+                LockEntry::file("src/main.rs".to_string(), LockEntryStatus::Created, b"fn main() {}\n"),
+                LockEntry::file("README.md".to_string(), LockEntryStatus::Skipped, b"# demo\n"),
+            ],
+        }
+    }
+
+    #[test]
+    fn saves_and_loads_the_manifest() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let lockfile = lockfile();
+
+        lockfile.save(dir.path()).unwrap();
+
+        assert_eq!(Lockfile::load(dir.path()).unwrap(), lockfile);
+    }
+
+    #[test]
+    fn keeps_hash_and_content_only_for_written_files() {
+        let lockfile = lockfile();
+
+        let main = lockfile.entry("src/main.rs").unwrap();
+        assert_eq!(main.hash.as_deref(), Some(hash_bytes(b"fn main() {}\n").as_str()));
+        assert_eq!(main.content.as_deref(), Some("fn main() {}\n"));
+        let readme = lockfile.entry("README.md").unwrap();
+        assert_eq!((readme.hash.as_deref(), readme.content.as_deref()), (None, None));
+
+        let binary = LockEntry::file("logo.png".to_string(), LockEntryStatus::Created, &[0xff, 0xd8]);
+        assert!(binary.hash.is_some() && binary.content.is_none());
+    }
+
+    #[test]
+    fn loading_a_missing_manifest_names_the_directory() {
+        let dir = tempfile::TempDir::new().unwrap();
+
+        let error = Lockfile::load(dir.path()).unwrap_err();
+
+        assert!(matches!(error, ForgeTreeError::InvalidPath(_)), "{}", error);
+        assert!(error.to_string().contains(LOCKFILE_NAME), "{}", error);
+    }
+
+    #[test]
+    fn rejects_corrupt_manifests() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join(LOCKFILE_NAME), "{ not json").unwrap();
+
+        let error = Lockfile::load(dir.path()).unwrap_err();
+        assert!(error.to_string().contains("Invalid .forge-tree.lock"), "{}", error);
+
+        let mut future = lockfile();
+        future.version = LOCKFILE_VERSION + 1;
+        let error = Lockfile::from_json(&future.to_json().unwrap()).unwrap_err();
+        assert!(error.to_string().contains("Unsupported"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_paths_outside_the_project() {
+        for path in ["../escape.txt", "/etc/passwd", "src/../../x", "./src", ""] {
+            let mut lockfile = lockfile();
+            lockfile.entries[1].path = path.to_string();
+            assert!(Lockfile::from_json(&lockfile.to_json().unwrap()).is_err(), "{:?}", path);
+        }
+
+        let mut lockfile = lockfile();
+        lockfile.entries[1].backup = Some("../src/main.rs.orig".to_string());
+        assert!(Lockfile::from_json(&lockfile.to_json().unwrap()).is_err());
+    }
+
+    #[test]
+    fn project_paths_stay_inside_the_root() {
+        let root = Path::new("project");
+
+        assert_eq!(project_path(root, "src/main.rs").unwrap(), root.join("src").join("main.rs"));
+        assert!(project_path(root, "../x").is_err());
+        assert_eq!(slash_path(&Path::new("src").join("main.rs")), "src/main.rs");
+    }
+}
@@ -7,6 +7,6 @@ fn main() {
 
     if let Err(e) = Cli::run(matches) {
         eprintln!("{} {}", "Error:".red().bold(), e);
-        std::process::exit(1);
+        std::process::exit(e.exit_code());
     }
 }
@@ -0,0 +1,309 @@
+//! Fluent construction of a ProjectStructure in code
+//!
+//! ```
+//! use forge_tree::parser::ProjectStructure;
+//!
+//! let structure = ProjectStructure::builder("app")
+//!     .dir("src", |d| d.file("main.rs").content("fn main() {}"))
+//!     .file("Cargo.toml").template("[package]\nname = \"{{name}}\"\n")
+//!     .var("name", "app")
+//!     .build()
+//!     .unwrap();
+//!
+//! assert_eq!(structure.items.len(), 2);
+//! assert_eq!(structure.items[0].children[0].path, "src/main.rs");
+//! ```
+
+use crate::git::GitSettings;
+use crate::hooks::{Hook, HookStage, Hooks};
+use crate::parser::{find_duplicates, Condition, ItemType, ProjectStructure, StructureItem};
+use crate::variables::{check_declarations, VariableDecl, VariableDecls};
+use crate::{ForgeTreeError, Result};
+use std::collections::HashMap;
+
+/// Builds the entries of one directory level
+///
+/// Modifiers such as [`DirBuilder::content`] apply to the most recently added entry.
+#[derive(Debug, Default)]
+pub struct DirBuilder {
+    items: Vec<StructureItem>,
+    errors: Vec<String>,
+}
+
+impl DirBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a directory whose entries are built by the closure
+    pub fn dir<F>(mut self, name: &str, build: F) -> Self
+    where
+        F: FnOnce(DirBuilder) -> DirBuilder,
+    {
+        let children = build(DirBuilder::new());
+        let mut item = StructureItem::directory(name);
+        item.children = children.items;
+        self.errors.extend(children.errors);
+        self.items.push(item);
+        self
+    }
+
+    /// Add an empty file
+    pub fn file(mut self, name: &str) -> Self {
+        self.items.push(StructureItem::file(name));
+        self
+    }
+
+    /// Set literal content on the last added file
+    pub fn content(self, content: &str) -> Self {
+        self.modify_file("content", |item| item.content = Some(content.to_string()))
+    }
+
+    /// Set a handlebars template on the last added file
+    pub fn template(self, template: &str) -> Self {
+        self.modify_file("template", |item| item.template = Some(template.to_string()))
+    }
+
+    /// Only generate the last added entry when the variable is truthy
+    pub fn when(self, variable: &str) -> Self {
+        self.add_condition(Condition::If(variable.to_string()))
+    }
+
+    /// Only generate the last added entry when the variable is falsy
+    pub fn unless(self, variable: &str) -> Self {
+        self.add_condition(Condition::Unless(variable.to_string()))
+    }
+
+    /// Repeat the last added entry once per element of a list variable
+    pub fn each(self, list: &str) -> Self {
+        self.modify("each", |item| item.each = Some(list.to_string()))
+    }
+
+    /// Set Unix permission bits (e.g. `0o755`) on the last added entry
+    pub fn mode(self, mode: u32) -> Self {
+        self.modify("mode", |item| item.mode = Some(mode))
+    }
+
+    fn add_condition(self, condition: Condition) -> Self {
+        self.modify("condition", |item| {
+            item.condition = Some(match item.condition.take() {
+                Some(existing) => existing.and(condition),
+                None => condition,
+            });
+        })
+    }
+
+    fn modify<F: FnOnce(&mut StructureItem)>(mut self, what: &str, apply: F) -> Self {
+        match self.items.last_mut() {
+            Some(item) => apply(item),
+            None => self.errors.push(format!("{} set before any entry was added", what)),
+        }
+        self
+    }
+
+    fn modify_file<F: FnOnce(&mut StructureItem)>(mut self, what: &str, apply: F) -> Self {
+        match self.items.last_mut() {
+            Some(item) if matches!(item.item_type, ItemType::File) => apply(item),
+            Some(item) => {
+                let error = format!("{} set on directory '{}'", what, item.name);
+                self.errors.push(error);
+            }
+            None => self.errors.push(format!("{} set before any entry was added", what)),
+        }
+        self
+    }
+}
+
+/// Builds a whole [`ProjectStructure`]; created with [`ProjectStructure::builder`]
+#[derive(Debug)]
+pub struct StructureBuilder {
+    root: String,
+    entries: DirBuilder,
+    variables: HashMap<String, String>,
+    declarations: VariableDecls,
+    hooks: Hooks,
+    git: GitSettings,
+}
+
+impl StructureBuilder {
+    pub fn new(root: &str) -> Self {
+        Self {
+            root: root.to_string(),
+            entries: DirBuilder::new(),
+            variables: HashMap::new(),
+            declarations: VariableDecls::new(),
+            hooks: Hooks::default(),
+            git: GitSettings::default(),
+        }
+    }
+
+    /// Add a top-level directory whose entries are built by the closure
+    pub fn dir<F>(mut self, name: &str, build: F) -> Self
+    where
+        F: FnOnce(DirBuilder) -> DirBuilder,
+    {
+        self.entries = self.entries.dir(name, build);
+        self
+    }
+
+    /// Add an empty top-level file
+    pub fn file(mut self, name: &str) -> Self {
+        self.entries = self.entries.file(name);
+        self
+    }
+
+    /// Set literal content on the last added top-level file
+    pub fn content(mut self, content: &str) -> Self {
+        self.entries = self.entries.content(content);
+        self
+    }
+
+    /// Set a handlebars template on the last added top-level file
+    pub fn template(mut self, template: &str) -> Self {
+        self.entries = self.entries.template(template);
+        self
+    }
+
+    /// Only generate the last added top-level entry when the variable is truthy
+    pub fn when(mut self, variable: &str) -> Self {
+        self.entries = self.entries.when(variable);
+        self
+    }
+
+    /// Only generate the last added top-level entry when the variable is falsy
+    pub fn unless(mut self, variable: &str) -> Self {
+        self.entries = self.entries.unless(variable);
+        self
+    }
+
+    /// Repeat the last added top-level entry once per element of a list variable
+    pub fn each(mut self, list: &str) -> Self {
+        self.entries = self.entries.each(list);
+        self
+    }
+
+    /// Set Unix permission bits (e.g. `0o755`) on the last added top-level entry
+    pub fn mode(mut self, mode: u32) -> Self {
+        self.entries = self.entries.mode(mode);
+        self
+    }
+
+    /// Set a variable value
+    pub fn var(mut self, name: &str, value: &str) -> Self {
+        self.variables.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Declare a typed variable
+    pub fn declare(mut self, name: &str, declaration: VariableDecl) -> Self {
+        self.declarations.insert(name.to_string(), declaration);
+        self
+    }
+
+    /// Add a hook to run before or after generation
+    pub fn hook(mut self, stage: HookStage, hook: Hook) -> Self {
+        match stage {
+            HookStage::PreGenerate => self.hooks.pre_generate.push(hook),
+            HookStage::PostGenerate => self.hooks.post_generate.push(hook),
+        }
+        self
+    }
+
+    /// Add a pattern to the `.gitignore` written when generating with git
+    pub fn gitignore(mut self, pattern: &str) -> Self {
+        self.git.ignore.push(pattern.to_string());
+        self
+    }
+
+    /// Finish the structure, computing paths and rejecting duplicate siblings
+    pub fn build(self) -> Result<ProjectStructure> {
+        if let Some(error) = self.entries.errors.first() {
+            return Err(ForgeTreeError::InvalidPath(format!("Invalid structure: {}", error)));
+        }
+        if self.root.trim().is_empty() {
+            return Err(ForgeTreeError::InvalidPath("Root name must not be empty".to_string()));
+        }
+        check_declarations(&self.declarations)?;
+
+        let duplicates = find_duplicates(&self.entries.items);
+        if let Some(duplicate) = duplicates.first() {
+            return Err(ForgeTreeError::DuplicateEntry(duplicate.to_string()));
+        }
+
+        let mut structure = ProjectStructure {
+            root: self.root,
+            items: self.entries.items,
+            variables: self.variables,
+            declarations: self.declarations,
+            extends: None,
+            hooks: self.hooks,
+            git: self.git,
+        };
+        structure.recompute_paths();
+        Ok(structure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variables::VarType;
+
+    #[test]
+    fn nests_entries_and_computes_paths() {
+        let structure = ProjectStructure::builder("app")
+            .dir("src", |d| d.dir("bin", |d| d.file("cli.rs")).file("lib.rs"))
+            .file("README.md")
+            .build()
+            .unwrap();
+
+        let paths: Vec<_> = structure.iter().map(|item| item.path.as_str()).collect();
+        assert_eq!(paths, ["src", "src/bin", "src/bin/cli.rs", "src/lib.rs", "README.md"]);
+        assert_eq!(structure.find("src/bin").unwrap().item_type, ItemType::Directory);
+    }
+
+    #[test]
+    fn modifiers_apply_to_the_last_entry() {
+        let structure = ProjectStructure::builder("app")
+            .file("run.sh").content("#!/bin/sh\n").mode(0o755)
+            .dir("ci", |d| d).when("ci").unless("minimal")
+            .dir("{{item}}", |d| d).each("services")
+            .var("services", "auth")
+            .gitignore("target/")
+            .hook(HookStage::PostGenerate, Hook::run("make"))
+            .build()
+            .unwrap();
+
+        let run = structure.find("run.sh").unwrap();
+        assert_eq!((run.content.as_deref(), run.mode), (Some("#!/bin/sh\n"), Some(0o755)));
+        assert_eq!(
+            structure.find("ci").unwrap().condition,
+            Some(Condition::If("ci".to_string()).and(Condition::Unless("minimal".to_string())))
+        );
+        assert_eq!(structure.find("{{item}}").unwrap().each.as_deref(), Some("services"));
+        assert_eq!(structure.variables["services"], "auth");
+        assert_eq!(structure.git.ignore, ["target/"]);
+        assert_eq!(structure.hooks.post_generate.len(), 1);
+    }
+
+    #[test]
+    fn rejects_misplaced_modifiers() {
+        let content_on_dir = ProjectStructure::builder("app").dir("src", |d| d).content("x").build();
+        let nested = ProjectStructure::builder("app").dir("src", |d| d.template("x")).build();
+        let unnamed = ProjectStructure::builder(" ").build();
+
+        assert!(content_on_dir.unwrap_err().to_string().contains("content set on directory 'src'"));
+        assert!(nested.unwrap_err().to_string().contains("template set before any entry"));
+        assert!(unnamed.is_err());
+    }
+
+    #[test]
+    fn rejects_duplicates_and_invalid_declarations() {
+        let duplicate = ProjectStructure::builder("app").file("a.txt").file("a.txt").build();
+        assert!(matches!(duplicate, Err(ForgeTreeError::DuplicateEntry(_))));
+
+        let enum_without_values = VariableDecl { var_type: VarType::Enum, ..VariableDecl::default() };
+        let declared = ProjectStructure::builder("app").declare("license", enum_without_values).build();
+        assert!(declared.unwrap_err().to_string().contains("license"));
+    }
+}
@@ -0,0 +1,245 @@
+//! Detection and resolution of duplicate sibling entries
+//!
+//! A structure that lists the same name twice at one level (e.g. two `src/`
+//! directories) would otherwise be generated twice, or fail halfway through
+//! with a `FileExists` error. This module finds such siblings and applies a
+//! configurable [`DuplicatePolicy`] to them.
+
+use crate::parser::{ItemType, StructureItem};
+use crate::{ForgeTreeError, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// What to do when two siblings share the same name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Reject the structure
+    #[default]
+    Error,
+    /// Merge the children of duplicate directories (duplicate files are still an error)
+    Merge,
+    /// Keep only the last occurrence
+    LastWins,
+}
+
+impl FromStr for DuplicatePolicy {
+    type Err = ForgeTreeError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "error" => Ok(Self::Error),
+            "merge" => Ok(Self::Merge),
+            "last-wins" => Ok(Self::LastWins),
+            other => Err(ForgeTreeError::parse(format!(
+                "Unknown duplicate policy: {} (expected error, merge or last-wins)", other
+            ))),
+        }
+    }
+}
+
+/// A pair of siblings sharing the same name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicate {
+    /// Path of the duplicated entry relative to the project root
+    pub path: String,
+    /// Source line of the first occurrence, if known
+    pub first_line: Option<usize>,
+    /// Source line of the repeated occurrence, if known
+    pub second_line: Option<usize>,
+}
+
+impl fmt::Display for Duplicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.first_line, self.second_line) {
+            (Some(first), Some(second)) => write!(f, "'{}' at lines {} and {}", self.path, first, second),
+            _ => write!(f, "'{}'", self.path),
+        }
+    }
+}
+
+/// Find every duplicate sibling in the tree, in source order
+pub fn find_duplicates(items: &[StructureItem]) -> Vec<Duplicate> {
+    let mut duplicates = Vec::new();
+    collect_duplicates(items, "", &mut duplicates);
+    duplicates
+}
+
+fn collect_duplicates(items: &[StructureItem], prefix: &str, duplicates: &mut Vec<Duplicate>) {
+    for (index, item) in items.iter().enumerate() {
+        let path = join_path(prefix, &item.name);
+
+        // Report each later sibling against the first one with the same name
+        if let Some(first) = items[..index].iter().find(|other| same_entry(other, item)) {
+            duplicates.push(Duplicate {
+                path: path.clone(),
+                first_line: first.line,
+                second_line: item.line,
+            });
+        }
+
+        collect_duplicates(&item.children, &path, duplicates);
+    }
+}
+
+/// Apply the duplicate policy to the tree in place
+pub fn resolve_duplicates(items: &mut Vec<StructureItem>, policy: DuplicatePolicy) -> Result<()> {
+    resolve_level(items, policy, "")
+}
+
+fn resolve_level(items: &mut Vec<StructureItem>, policy: DuplicatePolicy, prefix: &str) -> Result<()> {
+    let mut resolved: Vec<StructureItem> = Vec::with_capacity(items.len());
+
+    for item in items.drain(..) {
+        let Some(existing) = resolved.iter_mut().find(|other| same_entry(other, &item)) else {
+            resolved.push(item);
+            continue;
+        };
+
+        let duplicate = Duplicate {
+            path: join_path(prefix, &item.name),
+            first_line: existing.line,
+            second_line: item.line,
+        };
+
+        match policy {
+            DuplicatePolicy::Error => {
+                return Err(ForgeTreeError::DuplicateEntry(duplicate.to_string()));
+            }
+            DuplicatePolicy::Merge => {
+                let both_directories = matches!(existing.item_type, ItemType::Directory)
+                    && matches!(item.item_type, ItemType::Directory);
+                if !both_directories {
+                    return Err(ForgeTreeError::DuplicateEntry(format!(
+                        "{} (only directories can be merged)", duplicate
+                    )));
+                }
+                existing.children.extend(item.children);
+            }
+            DuplicatePolicy::LastWins => {
+                // Keep the position of the first occurrence so ordering stays stable
+                *existing = item;
+            }
+        }
+    }
+
+    for item in resolved.iter_mut() {
+        let path = join_path(prefix, &item.name);
+        resolve_level(&mut item.children, policy, &path)?;
+    }
+
+    *items = resolved;
+    Ok(())
+}
+
+/// Siblings clash when they share a name and would be generated under the same condition
+///
+/// Entries like `config.yml [if a]` and `config.yml [unless a]` are alternatives, not duplicates.
+fn same_entry(left: &StructureItem, right: &StructureItem) -> bool {
+    left.name == right.name && left.condition == right.condition && left.each == right.each
+}
+
+fn join_path(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", prefix, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Condition, Parser};
+
+    const TWO_SRC: &str = "app/
+├── src/
+│   └── main.rs
+├── src/
+│   └── lib.rs
+└── README.md
+";
+
+    fn parse(input: &str, policy: DuplicatePolicy) -> Result<Vec<StructureItem>> {
+        Parser::new().with_duplicate_policy(policy).parse(input).map(|structure| structure.items)
+    }
+
+    fn names(items: &[StructureItem]) -> Vec<&str> {
+        items.iter().map(|item| item.name.as_str()).collect()
+    }
+
+    #[test]
+    fn finds_duplicates_with_their_lines() {
+        let structure = Parser::new().parse_unresolved(TWO_SRC).unwrap();
+
+        let duplicates = find_duplicates(&structure.items);
+
+        assert_eq!(duplicates, [Duplicate { path: "src".to_string(), first_line: Some(2), second_line: Some(4) }]);
+        assert_eq!(duplicates[0].to_string(), "'src' at lines 2 and 4");
+    }
+
+    #[test]
+    fn error_policy_rejects_duplicates() {
+        let error = parse(TWO_SRC, DuplicatePolicy::Error).unwrap_err();
+        assert!(matches!(error, ForgeTreeError::DuplicateEntry(_)), "{}", error);
+    }
+
+    #[test]
+    fn merge_policy_combines_directories() {
+        let items = parse(TWO_SRC, DuplicatePolicy::Merge).unwrap();
+
+        assert_eq!(names(&items), ["src", "README.md"]);
+        assert_eq!(names(&items[0].children), ["main.rs", "lib.rs"]);
+    }
+
+    #[test]
+    fn merge_policy_rejects_duplicate_files() {
+        let error = parse("app/\n├── README.md\n└── README.md\n", DuplicatePolicy::Merge).unwrap_err();
+        assert!(error.to_string().contains("only directories can be merged"), "{}", error);
+    }
+
+    #[test]
+    fn last_wins_keeps_the_last_occurrence_in_the_first_position() {
+        let items = parse(TWO_SRC, DuplicatePolicy::LastWins).unwrap();
+
+        assert_eq!(names(&items), ["src", "README.md"]);
+        assert_eq!(names(&items[0].children), ["lib.rs"]);
+    }
+
+    #[test]
+    fn entries_under_different_conditions_are_not_merged() {
+        let input = "app/
+├── config/  [if docker]
+│   └── docker.yml
+├── config/  [if kubernetes]
+│   └── k8s.yml
+├── config/  [if docker]
+│   └── compose.yml
+└── README.md
+";
+        assert!(parse(input, DuplicatePolicy::Error).is_err());
+
+        let items = parse(input, DuplicatePolicy::Merge).unwrap();
+
+        assert_eq!(names(&items), ["config", "config", "README.md"]);
+        assert_eq!(items[0].condition, Some(Condition::If("docker".to_string())));
+        assert_eq!(names(&items[0].children), ["docker.yml", "compose.yml"]);
+        assert_eq!(items[1].condition, Some(Condition::If("kubernetes".to_string())));
+        assert_eq!(names(&items[1].children), ["k8s.yml"]);
+    }
+
+    #[test]
+    fn parse_file_reports_duplicates_the_policy_rejected() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("app.tree");
+        std::fs::write(&path, TWO_SRC).unwrap();
+        let path = path.to_str().unwrap();
+
+        let (duplicates, result) = Parser::new().parse_file_with_duplicates(path);
+        assert_eq!(duplicates.len(), 1);
+        assert!(result.is_err());
+
+        let (duplicates, result) = Parser::new().with_duplicate_policy(DuplicatePolicy::Merge).parse_file_with_duplicates(path);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(names(&result.unwrap().items), ["src", "README.md"]);
+    }
+}
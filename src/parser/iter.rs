@@ -0,0 +1,159 @@
+//! Depth-first traversal of a ProjectStructure
+
+use crate::parser::{ProjectStructure, StructureItem};
+
+/// Pre-order iterator over every item, created by [`ProjectStructure::walk`]
+///
+/// Yields each item with its depth (0 for top-level items). Parents always
+/// come before their children, and siblings keep their order.
+pub struct Walk<'a> {
+    stack: Vec<(usize, std::slice::Iter<'a, StructureItem>)>,
+}
+
+impl<'a> Walk<'a> {
+    pub(crate) fn new(items: &'a [StructureItem]) -> Self {
+        Self { stack: vec![(0, items.iter())] }
+    }
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = (usize, &'a StructureItem);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (depth, siblings) = self.stack.last_mut()?;
+            let depth = *depth;
+
+            match siblings.next() {
+                Some(item) => {
+                    if !item.children.is_empty() {
+                        self.stack.push((depth + 1, item.children.iter()));
+                    }
+                    return Some((depth, item));
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Pre-order iterator over every item, created by [`ProjectStructure::iter`]
+pub struct Iter<'a> {
+    walk: Walk<'a>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a StructureItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.walk.next().map(|(_, item)| item)
+    }
+}
+
+impl ProjectStructure {
+    /// Iterate over every item in the tree, parents before children
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { walk: self.walk() }
+    }
+
+    /// Iterate over every item in the tree together with its depth
+    pub fn walk(&self) -> Walk<'_> {
+        Walk::new(&self.items)
+    }
+
+    /// Look up an item by its `/`-separated path relative to the root
+    pub fn find(&self, path: &str) -> Option<&StructureItem> {
+        let mut items = &self.items;
+        let mut found = None;
+
+        for name in split_path(path) {
+            let item = items.iter().find(|item| item.name == name)?;
+            items = &item.children;
+            found = Some(item);
+        }
+
+        found
+    }
+
+    /// Mutable variant of [`ProjectStructure::find`]
+    ///
+    /// Renaming an item or moving children around leaves `path` stale; call
+    /// [`ProjectStructure::recompute_paths`] afterwards.
+    pub fn get_mut(&mut self, path: &str) -> Option<&mut StructureItem> {
+        let mut names = split_path(path);
+        let first = names.next()?;
+        let mut item = self.items.iter_mut().find(|item| item.name == first)?;
+
+        for name in names {
+            item = item.children.iter_mut().find(|child| child.name == name)?;
+        }
+
+        Some(item)
+    }
+}
+
+impl<'a> IntoIterator for &'a ProjectStructure {
+    type Item = &'a StructureItem;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty() && *segment != ".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn structure() -> ProjectStructure {
+        ProjectStructure::builder("app")
+            .dir("src", |d| d.dir("bin", |d| d.file("cli.rs")).file("lib.rs"))
+            .dir("docs", |d| d)
+            .file("README.md")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn walks_parents_before_children_with_their_depth() {
+        let structure = structure();
+
+        let walked: Vec<_> = structure.walk().map(|(depth, item)| (depth, item.path.as_str())).collect();
+
+        assert_eq!(
+            walked,
+            [(0, "src"), (1, "src/bin"), (2, "src/bin/cli.rs"), (1, "src/lib.rs"), (0, "docs"), (0, "README.md")]
+        );
+        assert_eq!((&structure).into_iter().count(), 6);
+        assert_eq!(ProjectStructure::builder("empty").build().unwrap().iter().count(), 0);
+    }
+
+    #[test]
+    fn finds_items_by_path() {
+        let structure = structure();
+
+        assert_eq!(structure.find("src/bin/cli.rs").unwrap().name, "cli.rs");
+        assert_eq!(structure.find("./src//lib.rs/").unwrap().path, "src/lib.rs");
+        assert!(structure.find("src/missing.rs").is_none());
+        assert!(structure.find("README.md/child").is_none());
+        assert!(structure.find("").is_none());
+    }
+
+    #[test]
+    fn changes_items_in_place_through_get_mut() {
+        let mut structure = structure();
+
+        structure.get_mut("src/bin").unwrap().name = "tools".to_string();
+        assert_eq!(structure.find("src/tools/cli.rs").unwrap().path, "src/bin/cli.rs");
+
+        structure.recompute_paths();
+        assert_eq!(structure.find("src/tools/cli.rs").unwrap().path, "src/tools/cli.rs");
+        assert!(structure.get_mut("src/bin").is_none());
+    }
+}
@@ -1,53 +1,318 @@
+pub mod builder;
+pub mod duplicates;
+pub mod iter;
+pub mod overlay;
 pub mod tree_parser;
+pub mod tree_writer;
+pub mod visitor;
 
+pub use builder::{DirBuilder, StructureBuilder};
+pub use duplicates::{find_duplicates, resolve_duplicates, Duplicate, DuplicatePolicy};
+pub use iter::{Iter, Walk};
+pub use overlay::apply_overlay;
 pub use tree_parser::TreeParser;
+pub use tree_writer::TreeWriter;
+pub use visitor::{walk, StructureStats, StructureVisitor, Visit};
 
-use crate::{Result, ForgeTreeError};
+use crate::git::GitSettings;
+use crate::hooks::Hooks;
+use crate::variables::{self, VariableDecls};
+use crate::{ForgeTreeError, Result};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectStructure {
     pub root: String,
     pub items: Vec<StructureItem>,
     pub variables: HashMap<String, String>,
+    /// Typed variable declarations from the structure's front matter
+    #[serde(default)]
+    pub declarations: VariableDecls,
+    /// Base structure this one overlays, as written in the front matter (cleared once resolved)
+    #[serde(default)]
+    pub extends: Option<String>,
+    /// Commands run before and after generation
+    #[serde(default)]
+    pub hooks: Hooks,
+    /// `.gitignore` patterns and initial commit defaults used with `--git`
+    #[serde(default)]
+    pub git: GitSettings,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StructureItem {
     pub name: String,
+    /// Location relative to the project root, with `/` separators (e.g. `src/main.rs`)
     pub path: String,
     pub item_type: ItemType,
     pub template: Option<String>,
     pub content: Option<String>,
     pub children: Vec<StructureItem>,
+    /// 1-based line in the source text, if the item was parsed
+    pub line: Option<usize>,
+    /// Guard deciding whether the item (and its children) is generated
+    pub condition: Option<Condition>,
+    /// List variable this entry is repeated over, once per element (`[each list]`)
+    pub each: Option<String>,
+    /// How this entry modifies the base structure when overlaying with `extends`
+    pub overlay: Option<OverlayOp>,
+    /// Unix permission bits to apply once created (`[mode=755]`, or a trailing `*` for `755`)
+    pub mode: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl ProjectStructure {
+    /// Start building a structure in code
+    pub fn builder(root: &str) -> StructureBuilder {
+        StructureBuilder::new(root)
+    }
+
+    /// Set every item's `path` to its `/`-separated location relative to the root
+    pub fn recompute_paths(&mut self) {
+        assign_paths(&mut self.items, "");
+    }
+}
+
+fn assign_paths(items: &mut [StructureItem], prefix: &str) {
+    for item in items {
+        item.path = if prefix.is_empty() {
+            item.name.clone()
+        } else {
+            format!("{}/{}", prefix, item.name)
+        };
+        assign_paths(&mut item.children, &item.path);
+    }
+}
+
+impl StructureItem {
+    /// A file entry with no content, template or annotations
+    pub fn file(name: &str) -> Self {
+        Self::new(name, ItemType::File)
+    }
+
+    /// A directory entry with no children or annotations
+    pub fn directory(name: &str) -> Self {
+        Self::new(name, ItemType::Directory)
+    }
+
+    /// A symlink entry pointing at `target`, relative to the link's directory unless absolute
+    pub fn symlink(name: &str, target: &str) -> Self {
+        Self::new(name, ItemType::Symlink { target: target.to_string() })
+    }
+
+    fn new(name: &str, item_type: ItemType) -> Self {
+        Self {
+            name: name.to_string(),
+            path: name.to_string(),
+            item_type,
+            template: None,
+            content: None,
+            children: Vec::new(),
+            line: None,
+            condition: None,
+            each: None,
+            overlay: None,
+            mode: None,
+        }
+    }
+}
+
+/// Separator between a symlink's name and its target, as printed by `tree`
+pub const SYMLINK_ARROW: &str = " -> ";
+
+/// Mode given to files marked executable with a trailing `*`, like `ls -F` shows them
+pub const EXECUTABLE_MODE: u32 = 0o755;
+
+/// Parse permission bits written in octal (`755`, `0755` or `0o755`)
+pub fn parse_mode(mode: &str) -> Option<u32> {
+    let digits = mode.strip_prefix("0o").unwrap_or(mode);
+    u32::from_str_radix(digits, 8).ok().filter(|mode| *mode <= 0o7777)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ItemType {
     Directory,
     File,
+    /// A symbolic link (`current -> releases/v1`); the target may use template variables
+    Symlink { target: String },
+}
+
+/// Explicit overlay operations on a base structure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverlayOp {
+    /// Delete the base entry (`-legacy/`)
+    Remove,
+    /// Replace the base entry and its whole subtree instead of merging (`[replace]`)
+    Replace,
+}
+
+/// A guard attached to an entry with `[if var]`/`[unless var]` or a `{{#if var}}` block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Condition {
+    /// True when the variable is truthy
+    If(String),
+    /// True when the variable is missing or falsy
+    Unless(String),
+    /// True when every inner condition is true
+    All(Vec<Condition>),
+}
+
+impl Condition {
+    /// Evaluate against a rendering context
+    pub fn evaluate(&self, context: &Value) -> bool {
+        match self {
+            Condition::If(name) => context.get(name).is_some_and(variables::is_truthy),
+            Condition::Unless(name) => !context.get(name).is_some_and(variables::is_truthy),
+            Condition::All(conditions) => conditions.iter().all(|condition| condition.evaluate(context)),
+        }
+    }
+
+    /// Combine two conditions so both must hold
+    pub fn and(self, other: Condition) -> Condition {
+        match (self, other) {
+            (Condition::All(mut left), Condition::All(right)) => {
+                left.extend(right);
+                Condition::All(left)
+            }
+            (Condition::All(mut left), right) => {
+                left.push(right);
+                Condition::All(left)
+            }
+            (left, Condition::All(mut right)) => {
+                right.insert(0, left);
+                Condition::All(right)
+            }
+            (left, right) => Condition::All(vec![left, right]),
+        }
+    }
+
+    /// Block keyword used to open this condition
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Condition::Unless(_) => "unless",
+            _ => "if",
+        }
+    }
+
+    /// The first inner condition that is false, for explaining exclusions
+    pub fn first_failure(&self, context: &Value) -> Option<&Condition> {
+        match self {
+            Condition::All(conditions) => conditions.iter().find_map(|condition| condition.first_failure(context)),
+            condition if !condition.evaluate(context) => Some(condition),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::If(name) => write!(f, "if {}", name),
+            Condition::Unless(name) => write!(f, "unless {}", name),
+            Condition::All(conditions) => {
+                let parts: Vec<String> = conditions.iter().map(Condition::to_string).collect();
+                f.write_str(&parts.join(", "))
+            }
+        }
+    }
 }
 
 pub struct Parser {
     tree_parser: TreeParser,
+    duplicate_policy: DuplicatePolicy,
 }
 
 impl Parser {
     pub fn new() -> Self {
         Self {
             tree_parser: TreeParser::new(),
+            duplicate_policy: DuplicatePolicy::default(),
         }
     }
 
+    /// Configure how siblings with the same name are handled
+    pub fn with_duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = policy;
+        self
+    }
+
+    pub fn duplicate_policy(&self) -> DuplicatePolicy {
+        self.duplicate_policy
+    }
+
     pub fn parse(&self, input: &str) -> Result<ProjectStructure> {
-        self.tree_parser.parse(input)
+        let mut structure = self.parse_unresolved(input)?;
+        if let Some(base) = &structure.extends {
+            return Err(ForgeTreeError::parse(format!(
+                "extends: {} needs a structure file to resolve against", base
+            )));
+        }
+        resolve_duplicates(&mut structure.items, self.duplicate_policy)?;
+        overlay::check_resolved(&structure.items)?;
+        Ok(structure)
     }
 
+    /// Parse a structure file, resolving `@include` directives and `extends` bases
     pub fn parse_file(&self, path: &str) -> Result<ProjectStructure> {
-        let content = std::fs::read_to_string(path)
-            .map_err(|e| ForgeTreeError::Io(e))?;
-        self.parse(&content)
+        self.parse_file_with_duplicates(path).1
+    }
+
+    /// Like [`Parser::parse_file`], also returning every duplicate sibling found before the policy was applied
+    ///
+    /// The duplicates are returned even when the policy rejects them.
+    pub fn parse_file_with_duplicates(&self, path: &str) -> (Vec<Duplicate>, Result<ProjectStructure>) {
+        let mut duplicates = Vec::new();
+        let result = self.parse_file_with_bases(Path::new(path), &mut Vec::new(), &mut duplicates)
+            .and_then(|structure| {
+                overlay::check_resolved(&structure.items)?;
+                Ok(structure)
+            });
+        (duplicates, result)
+    }
+
+    /// Parse a file and, if it extends a base, overlay it onto the fully resolved base
+    fn parse_file_with_bases(
+        &self,
+        path: &Path,
+        chain: &mut Vec<PathBuf>,
+        duplicates: &mut Vec<Duplicate>,
+    ) -> Result<ProjectStructure> {
+        let mut structure = self.tree_parser.parse_file(path)?;
+        duplicates.extend(find_duplicates(&structure.items));
+        resolve_duplicates(&mut structure.items, self.duplicate_policy)?;
+
+        let Some(base_name) = structure.extends.clone() else {
+            overlay::check_resolved(&structure.items).map_err(|e| e.in_file(path))?;
+            return Ok(structure);
+        };
+
+        chain.push(path.to_path_buf());
+        let base_path = overlay::locate_base(path, &base_name)?;
+        let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if chain.iter().any(|visited| canonical(visited) == canonical(&base_path)) {
+            let cycle: Vec<String> = chain.iter().map(|visited| visited.display().to_string()).collect();
+            return Err(ForgeTreeError::parse(format!(
+                "extends cycle: {} → {}", cycle.join(" → "), base_path.display()
+            )));
+        }
+
+        let base = self.parse_file_with_bases(&base_path, chain, duplicates)?;
+        apply_overlay(base, structure).map_err(|e| e.in_file(path))
+    }
+
+    /// Parse without applying the duplicate policy, keeping repeated siblings as written
+    pub fn parse_unresolved(&self, input: &str) -> Result<ProjectStructure> {
+        self.tree_parser.parse(input)
+    }
+
+    /// File variant of [`Parser::parse_unresolved`]
+    ///
+    /// `@include` directives are resolved relative to the file.
+    pub fn parse_file_unresolved(&self, path: &str) -> Result<ProjectStructure> {
+        self.tree_parser.parse_file(path)
     }
 }
 
@@ -0,0 +1,128 @@
+//! Structure inheritance with `extends`
+//!
+//! A structure whose front matter says `extends: base-service` is an overlay:
+//! its entries are merged onto the base structure by path. Directories merge
+//! recursively, other entries replace the base entry with the same name,
+//! `-name` deletes a base entry and `[replace]` swaps a whole base subtree
+//! instead of merging into it. Hooks of the base run before the overlay's, and
+//! the overlay's `.gitignore` patterns are added to the base's.
+
+use crate::parser::{ItemType, OverlayOp, ProjectStructure, StructureItem};
+use crate::{ForgeTreeError, Result};
+use std::path::{Path, PathBuf};
+
+/// Extensions tried when an `extends` name doesn't point at an existing file
+const BASE_EXTENSIONS: [&str; 2] = ["tree", "txt"];
+
+/// Merge an overlay structure onto its base
+///
+/// The overlay's root name, variables and declarations win over the base's.
+/// The result no longer refers to a base (`extends` is cleared).
+pub fn apply_overlay(base: ProjectStructure, overlay: ProjectStructure) -> Result<ProjectStructure> {
+    let mut merged = base;
+
+    merge_items(&mut merged.items, overlay.items, "")?;
+    merged.root = overlay.root;
+    merged.variables.extend(overlay.variables);
+    merged.declarations.extend(overlay.declarations);
+    // Base hooks run first, like a base's files are written first
+    merged.hooks.extend(overlay.hooks);
+    merged.git.extend(overlay.git);
+    merged.extends = None;
+    merged.recompute_paths();
+
+    Ok(merged)
+}
+
+fn merge_items(base: &mut Vec<StructureItem>, overlay: Vec<StructureItem>, prefix: &str) -> Result<()> {
+    for mut item in overlay {
+        let path = if prefix.is_empty() { item.name.clone() } else { format!("{}/{}", prefix, item.name) };
+        let existing = base.iter().position(|candidate| candidate.name == item.name);
+
+        match (item.overlay.take(), existing) {
+            (Some(OverlayOp::Remove), Some(index)) => {
+                base.remove(index);
+            }
+            (Some(OverlayOp::Remove), None) => {
+                return Err(ForgeTreeError::Parse {
+                    message: format!("Cannot remove '{}': it is not in the base structure", path),
+                    file: None,
+                    line: item.line,
+                });
+            }
+            (Some(OverlayOp::Replace), Some(index)) => {
+                check_resolved(&item.children)?;
+                base[index] = item;
+            }
+            (None, Some(index)) if is_directory(&base[index]) && is_directory(&item) => {
+                let target = &mut base[index];
+
+                // Annotations written on the overlay entry take precedence
+                if item.condition.is_some() {
+                    target.condition = item.condition.take();
+                }
+                if item.each.is_some() {
+                    target.each = item.each.take();
+                }
+                if item.mode.is_some() {
+                    target.mode = item.mode;
+                }
+                merge_items(&mut target.children, item.children, &path)?;
+            }
+            (None, Some(index)) => {
+                check_resolved(&item.children)?;
+                base[index] = item;
+            }
+            (_, None) => {
+                check_resolved(&item.children)?;
+                base.push(item);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Ensure no overlay markers are left in a structure that isn't being merged
+pub fn check_resolved(items: &[StructureItem]) -> Result<()> {
+    for item in items {
+        if let Some(op) = item.overlay {
+            let marker = match op {
+                OverlayOp::Remove => format!("-{}", item.name),
+                OverlayOp::Replace => format!("{} [replace]", item.name),
+            };
+            let location = item.line.map(|line| format!(" at line {}", line)).unwrap_or_default();
+            return Err(ForgeTreeError::Parse {
+                message: format!("'{}'{} only makes sense in a structure that extends a base", marker, location),
+                file: None,
+                line: item.line,
+            });
+        }
+        check_resolved(&item.children)?;
+    }
+
+    Ok(())
+}
+
+/// Find the file named by `extends`, relative to the extending file
+///
+/// `extends: base-service` matches `base-service`, `base-service.tree` or
+/// `base-service.txt`, in that order.
+pub fn locate_base(extending_file: &Path, base: &str) -> Result<PathBuf> {
+    let directory = extending_file.parent().unwrap_or(Path::new(""));
+    let candidate = directory.join(base);
+    if candidate.is_file() {
+        return Ok(candidate);
+    }
+
+    BASE_EXTENSIONS.iter()
+        .map(|extension| directory.join(format!("{}.{}", base, extension)))
+        .find(|path| path.is_file())
+        .ok_or_else(|| ForgeTreeError::parse(format!(
+            "Base structure '{}' (extended by {}) not found", base, extending_file.display()
+        )))
+}
+
+fn is_directory(item: &StructureItem) -> bool {
+    matches!(item.item_type, ItemType::Directory)
+}
@@ -1,6 +1,57 @@
-use crate::parser::{ItemType, ProjectStructure, StructureItem};
+use crate::git::GitSettings;
+use crate::hooks::Hooks;
+use crate::parser::{
+    parse_mode, Condition, ItemType, OverlayOp, ProjectStructure, StructureItem, EXECUTABLE_MODE, SYMLINK_ARROW,
+};
+use crate::variables::{check_declarations, VariableDecls};
 use crate::{Result, ForgeTreeError};
+use serde::Deserialize;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Delimiter line around the optional YAML front matter block
+const FRONT_MATTER_DELIMITER: &str = "---";
+
+/// Settings declared in the YAML block at the top of a structure file
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FrontMatter {
+    #[serde(default)]
+    variables: VariableDecls,
+    /// Base structure this one overlays
+    #[serde(default)]
+    extends: Option<String>,
+    /// Commands run before and after generation
+    #[serde(default)]
+    hooks: Hooks,
+    /// `.gitignore` patterns and initial commit defaults
+    #[serde(default)]
+    git: GitSettings,
+}
+
+/// Directive that splices another structure file's items in place
+const INCLUDE_DIRECTIVE: &str = "@include";
+
+/// An `@include` currently being expanded: the including file and the directive's line
+#[derive(Debug, Clone)]
+struct IncludeFrame {
+    file: PathBuf,
+    line: usize,
+}
+
+/// State threaded through a single parse
+struct ParseState {
+    /// File being parsed, used to resolve relative includes
+    file: Option<PathBuf>,
+    /// Includes that led to this file, outermost first
+    stack: Vec<IncludeFrame>,
+    /// Declarations picked up from included files' front matter
+    included_declarations: VariableDecls,
+    /// Hooks picked up from included files' front matter
+    included_hooks: Hooks,
+    /// Git settings picked up from included files' front matter
+    included_git: GitSettings,
+}
 
 pub struct TreeParser;
 
@@ -9,66 +60,266 @@ impl TreeParser {
         Self
     }
 
+    /// Parse structure text that isn't backed by a file
+    ///
+    /// `@include` directives need a file to resolve against, so they are
+    /// rejected here; use [`TreeParser::parse_file`] instead.
     pub fn parse(&self, input: &str) -> Result<ProjectStructure> {
-        let lines: Vec<&str> = input.lines().collect();
+        self.parse_source(input, ParseState {
+            file: None,
+            stack: Vec::new(),
+            included_declarations: VariableDecls::new(),
+            included_hooks: Hooks::default(),
+            included_git: GitSettings::default(),
+        })
+    }
+
+    /// Parse a structure file, resolving `@include` directives relative to it
+    pub fn parse_file<P: AsRef<Path>>(&self, path: P) -> Result<ProjectStructure> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        self.parse_source(&content, ParseState {
+            file: Some(path.to_path_buf()),
+            stack: Vec::new(),
+            included_declarations: VariableDecls::new(),
+            included_hooks: Hooks::default(),
+            included_git: GitSettings::default(),
+        })
+        .map_err(|e| e.in_file(path))
+    }
+
+    fn parse_source(&self, input: &str, mut state: ParseState) -> Result<ProjectStructure> {
+        // Keep 1-based line numbers alongside each line for error reporting
+        let all_lines: Vec<(usize, &str)> = input.lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line))
+            .collect();
+
+        let (mut front_matter, body) = self.split_front_matter(&all_lines)?;
+        if let Some(file) = &state.file {
+            front_matter.hooks.set_source(file);
+        }
+        check_declarations(&front_matter.variables)?;
+
+        let lines: Vec<(usize, &str)> = body.iter()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .copied()
+            .collect();
         if lines.is_empty() {
-            return Err(ForgeTreeError::Parse("Empty input".to_string()));
+            return Err(ForgeTreeError::parse("Empty input".to_string()));
         }
 
         let root_name = self.extract_root_name(&lines)?;
         
         // Parse all lines after the root
-        let child_lines: Vec<&str> = lines[1..].iter()
-            .filter(|line| !line.trim().is_empty())
-            .copied()
-            .collect();
+        let child_lines = &lines[1..];
         
-        let items = self.parse_structure(&child_lines)?;
+        let items = self.parse_structure(child_lines, &mut state)?;
 
-        Ok(ProjectStructure {
+        // The including file's own declarations take precedence
+        let mut declarations = state.included_declarations;
+        declarations.extend(front_matter.variables);
+        // Included files' hooks run before the including file's own
+        let mut hooks = state.included_hooks;
+        hooks.extend(front_matter.hooks);
+        let mut git = state.included_git;
+        git.extend(front_matter.git);
+
+        let mut structure = ProjectStructure {
             root: root_name,
             items,
             variables: HashMap::new(),
-        })
+            declarations,
+            extends: front_matter.extends,
+            hooks,
+            git,
+        };
+        // Included items were parsed relative to their own file; fix up every path here
+        structure.recompute_paths();
+        Ok(structure)
+    }
+
+    /// Parse the file named by an `@include` directive and return its items
+    fn parse_include(&self, target: &str, line_number: usize, state: &mut ParseState) -> Result<Vec<StructureItem>> {
+        let Some(file) = &state.file else {
+            return Err(ForgeTreeError::Include {
+                message: format!(
+                    "{} {} at line {} needs a structure file to resolve against",
+                    INCLUDE_DIRECTIVE, target, line_number
+                ),
+                file: None,
+                line: Some(line_number),
+            });
+        };
+
+        let included = normalize_path(&file.parent().unwrap_or(Path::new("")).join(target));
+        let mut stack = state.stack.clone();
+        stack.push(IncludeFrame { file: file.clone(), line: line_number });
+
+        // Compare canonical paths so `a/../b.tree` and `b.tree` are the same file
+        let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let included_canonical = canonical(&included);
+        if stack.iter().any(|frame| canonical(&frame.file) == included_canonical) {
+            return Err(include_error(&stack, format!(
+                "Include cycle: {} → {}", format_stack(&stack), included.display()
+            )));
+        }
+
+        let structure = std::fs::read_to_string(&included)
+            .map_err(ForgeTreeError::from)
+            .and_then(|content| self.parse_source(&content, ParseState {
+                file: Some(included.clone()),
+                stack: stack.clone(),
+                included_declarations: VariableDecls::new(),
+                included_hooks: Hooks::default(),
+                included_git: GitSettings::default(),
+            }));
+
+        match structure {
+            Ok(structure) => {
+                for (name, decl) in structure.declarations {
+                    state.included_declarations.entry(name).or_insert(decl);
+                }
+                state.included_hooks.extend(structure.hooks);
+                state.included_git.extend(structure.git);
+                Ok(structure.items)
+            }
+            // Errors from deeper includes already carry the full stack
+            Err(e @ ForgeTreeError::Include { .. }) => Err(e),
+            Err(e) => Err(include_error(&stack, format!(
+                "{} in {} (included from {})", e, included.display(), format_stack(&stack)
+            ))),
+        }
     }
 
-    fn extract_root_name(&self, lines: &[&str]) -> Result<String> {
-        let first_line = lines.first()
-            .ok_or_else(|| ForgeTreeError::Parse("No root directory found".to_string()))?;
+    /// Separate a leading `---` delimited YAML block from the tree lines
+    fn split_front_matter<'a>(&self, lines: &'a [(usize, &'a str)]) -> Result<(FrontMatter, &'a [(usize, &'a str)])> {
+        let start = lines.iter().position(|(_, line)| !line.trim().is_empty());
+        let Some(start) = start.filter(|&start| lines[start].1.trim() == FRONT_MATTER_DELIMITER) else {
+            return Ok((FrontMatter::default(), lines));
+        };
+
+        let end = lines[start + 1..].iter()
+            .position(|(_, line)| line.trim() == FRONT_MATTER_DELIMITER)
+            .map(|offset| start + 1 + offset)
+            .ok_or_else(|| ForgeTreeError::parse_at(lines[start].0, format!(
+                "Unterminated front matter starting at line {}", lines[start].0
+            )))?;
+
+        let yaml: Vec<&str> = lines[start + 1..end].iter().map(|(_, line)| *line).collect();
+        let yaml = yaml.join("\n");
+        let front_matter = if yaml.trim().is_empty() {
+            FrontMatter::default()
+        } else {
+            serde_yaml::from_str(&yaml).map_err(|e| ForgeTreeError::parse_at(lines[start].0, format!(
+                "Invalid front matter (starting at line {}): {}", lines[start].0, e
+            )))?
+        };
+
+        Ok((front_matter, &lines[end + 1..]))
+    }
+
+    fn extract_root_name(&self, lines: &[(usize, &str)]) -> Result<String> {
+        let (line_number, first_line) = lines.first()
+            .ok_or_else(|| ForgeTreeError::parse("No root directory found".to_string()))?;
         
         let name = first_line.trim().trim_end_matches('/');
         if name.is_empty() {
-            return Err(ForgeTreeError::Parse("Invalid root directory name".to_string()));
+            return Err(ForgeTreeError::parse_at(*line_number, "Invalid root directory name".to_string()));
         }
         
         Ok(name.to_string())
     }
 
-    fn parse_structure(&self, lines: &[&str]) -> Result<Vec<StructureItem>> {
+    fn parse_structure(&self, lines: &[(usize, &str)], state: &mut ParseState) -> Result<Vec<StructureItem>> {
         let mut items = Vec::new();
+        // Open `{{#if}}`/`{{#unless}}` blocks at this level, with the line they started on
+        let mut blocks: Vec<OpenBlock> = Vec::new();
         let mut i = 0;
 
         while i < lines.len() {
-            let line = lines[i];
+            let (line_number, line) = lines[i];
             let current_depth = self.get_depth(line);
             
-            let (name, is_directory) = self.parse_line(line)?;
-            let mut item = StructureItem {
-                name: name.clone(),
-                path: name,
-                item_type: if is_directory { ItemType::Directory } else { ItemType::File },
-                template: None,
-                content: None,
-                children: Vec::new(),
+            let (name, kind, annotations) = match self.parse_line(line).map_err(|e| e.at_line(line_number))? {
+                ParsedLine::Entry { name, kind, annotations } => (name, kind, annotations),
+                ParsedLine::Include { target, annotations } => {
+                    if lines.get(i + 1).is_some_and(|(_, next)| self.get_depth(next) > current_depth) {
+                        return Err(ForgeTreeError::parse_at(line_number, format!(
+                            "{} at line {} cannot have children", INCLUDE_DIRECTIVE, line_number
+                        )));
+                    }
+
+                    // Guards on the directive and enclosing blocks apply to every spliced item
+                    for mut item in self.parse_include(&target, line_number, state)? {
+                        self.apply_annotations(&mut item, &annotations, line_number)?;
+                        self.apply_blocks(&mut item, &blocks);
+                        items.push(item);
+                    }
+                    i += 1;
+                    continue;
+                }
+                ParsedLine::BlockStart(condition) => {
+                    blocks.push(OpenBlock { keyword: condition.keyword(), condition, line_number });
+                    i += 1;
+                    continue;
+                }
+                ParsedLine::BlockElse => {
+                    let block = blocks.last_mut().ok_or_else(|| ForgeTreeError::parse_at(
+                        line_number,
+                        format!("{{{{else}}}} without an open block at line {}", line_number)
+                    ))?;
+                    block.condition = match &block.condition {
+                        Condition::If(name) => Condition::Unless(name.clone()),
+                        Condition::Unless(name) => Condition::If(name.clone()),
+                        Condition::All(_) => unreachable!("blocks only hold single-variable conditions"),
+                    };
+                    i += 1;
+                    continue;
+                }
+                ParsedLine::BlockEnd(keyword) => {
+                    match blocks.pop() {
+                        Some(block) if block.keyword == keyword => {}
+                        _ => return Err(ForgeTreeError::parse_at(line_number, format!(
+                            "Unexpected {{{{/{}}}}} at line {}", keyword, line_number
+                        ))),
+                    }
+                    i += 1;
+                    continue;
+                }
             };
 
+            // A leading `-` marks an entry to delete from the base structure
+            let (name, overlay) = match name.strip_prefix('-').filter(|rest| !rest.is_empty()) {
+                Some(rest) => (rest.to_string(), Some(OverlayOp::Remove)),
+                None => (name, None),
+            };
+
+            let inferred_directory = matches!(kind, EntryKind::Directory { inferred: true });
+            let mut item = match kind {
+                EntryKind::Directory { .. } => StructureItem::directory(&name),
+                EntryKind::File { executable } => {
+                    let mut item = StructureItem::file(&name);
+                    if executable {
+                        item.mode = Some(EXECUTABLE_MODE);
+                    }
+                    item
+                }
+                EntryKind::Symlink { target } => StructureItem::symlink(&name, &target),
+            };
+            item.line = Some(line_number);
+            item.overlay = overlay;
+            self.apply_annotations(&mut item, &annotations, line_number)?;
+
+            self.apply_blocks(&mut item, &blocks);
+
             // Collect children (lines with greater depth)
             i += 1;
             let mut child_lines = Vec::new();
             
             while i < lines.len() {
                 let child_line = lines[i];
-                let child_depth = self.get_depth(child_line);
+                let child_depth = self.get_depth(child_line.1);
                 
                 // If we hit a line at same or less depth, stop collecting children
                 if child_depth <= current_depth {
@@ -80,32 +331,53 @@ impl TreeParser {
             }
 
             // Recursively parse children
+            if !child_lines.is_empty() && matches!(item.item_type, ItemType::Symlink { .. }) {
+                return Err(ForgeTreeError::parse_at(line_number, format!(
+                    "Symlink '{}' at line {} cannot have children", item.name, line_number
+                )));
+            }
             if !child_lines.is_empty() {
-                item.children = self.parse_structure(&child_lines)?;
+                item.children = self.parse_structure(&child_lines, state)?;
                 item.item_type = ItemType::Directory; // Has children, must be directory
+            } else if inferred_directory && !annotations.is_empty() {
+                // An annotated leaf like `Dockerfile  [if docker]` is a file; add `/` for an empty directory
+                item.item_type = ItemType::File;
             }
 
             items.push(item);
         }
 
+        if let Some(block) = blocks.last() {
+            return Err(ForgeTreeError::parse_at(block.line_number, format!(
+                "Unclosed {{{{#{}}}}} block starting at line {}", block.keyword, block.line_number
+            )));
+        }
+
         Ok(items)
     }
 
-    fn get_depth(&self, line: &str) -> usize {
-        let mut depth = 0;
-        
-        for ch in line.chars() {
-            match ch {
-                '├' | '└' | '│' => depth += 1,
-                '─' | ' ' => continue, // Skip dashes and spaces
-                _ => break, // Stop at first non-tree character
-            }
+    /// Entries inside open blocks inherit every enclosing condition
+    fn apply_blocks(&self, item: &mut StructureItem, blocks: &[OpenBlock]) {
+        for block in blocks.iter().rev() {
+            item.condition = Some(match item.condition.take() {
+                Some(existing) => block.condition.clone().and(existing),
+                None => block.condition.clone(),
+            });
         }
-        
-        depth
     }
 
-    fn parse_line(&self, line: &str) -> Result<(String, bool)> {
+    /// Indentation of an entry, measured as the width of its tree prefix
+    ///
+    /// Only relative depths matter, so the width works for both the compact
+    /// `│ ├── ` style and `tree`'s `│   ├── ` style, including children of a
+    /// last entry whose prefix is plain spaces (`    └── `).
+    fn get_depth(&self, line: &str) -> usize {
+        line.chars()
+            .take_while(|&ch| matches!(ch, '├' | '└' | '│' | '─' | ' '))
+            .count()
+    }
+
+    fn parse_line(&self, line: &str) -> Result<ParsedLine> {
         // Skip tree characters by counting characters, not bytes
         let content = line.chars()
             .skip_while(|&ch| ch == '│' || ch == '├' || ch == '└' || ch == '─' || ch == ' ')
@@ -114,17 +386,245 @@ impl TreeParser {
             .to_string();
         
         if content.is_empty() {
-            return Err(ForgeTreeError::Parse(format!("Empty name in line: {}", line)));
+            return Err(ForgeTreeError::parse(format!("Empty name in line: {}", line)));
+        }
+
+        if let Some(block) = self.parse_block_marker(&content)? {
+            return Ok(block);
         }
 
+        let (content, annotations) = self.split_annotations(&content);
+
+        if let Some(target) = content.strip_prefix(INCLUDE_DIRECTIVE).filter(|rest| rest.starts_with(char::is_whitespace)) {
+            return Ok(ParsedLine::Include { target: target.trim().to_string(), annotations });
+        }
+
+        // `name -> target` is a symlink, as `tree` prints them
+        if let Some((name, target)) = content.split_once(SYMLINK_ARROW) {
+            let (name, target) = (name.trim().trim_end_matches('/'), target.trim());
+            if name.is_empty() || target.is_empty() {
+                return Err(ForgeTreeError::parse(format!("Symlinks need a name and a target: {}", content)));
+            }
+            let kind = EntryKind::Symlink { target: target.to_string() };
+            return Ok(ParsedLine::Entry { name: name.to_string(), kind, annotations });
+        }
+
+        // A trailing `*` marks an executable file, so `bin/run*` is a file despite having no extension
+        let (content, executable) = match content.strip_suffix('*').filter(|name| !name.is_empty() && !name.ends_with('/')) {
+            Some(name) => (name.to_string(), true),
+            None => (content, false),
+        };
+
         // Determine if it's a directory or file
-        let is_directory = content.ends_with('/') || !content.contains('.');
+        let kind = if !executable && content.ends_with('/') {
+            EntryKind::Directory { inferred: false }
+        } else if !executable && !content.contains('.') {
+            EntryKind::Directory { inferred: true }
+        } else {
+            EntryKind::File { executable }
+        };
         let clean_name = content.trim_end_matches('/').to_string();
         
-        Ok((clean_name, is_directory))
+        Ok(ParsedLine::Entry { name: clean_name, kind, annotations })
+    }
+
+    /// Recognise `{{#if var}}`, `{{#unless var}}`, `{{else}}` and their closing tags
+    fn parse_block_marker(&self, content: &str) -> Result<Option<ParsedLine>> {
+        let Some(inner) = content.strip_prefix("{{").and_then(|rest| rest.strip_suffix("}}")) else {
+            return Ok(None);
+        };
+        let inner = inner.trim();
+
+        if inner == "else" {
+            return Ok(Some(ParsedLine::BlockElse));
+        }
+        if let Some(keyword) = inner.strip_prefix('/') {
+            return match keyword.trim() {
+                "if" => Ok(Some(ParsedLine::BlockEnd("if"))),
+                "unless" => Ok(Some(ParsedLine::BlockEnd("unless"))),
+                _ => Ok(None),
+            };
+        }
+        if let Some(opening) = inner.strip_prefix('#') {
+            let mut parts = opening.split_whitespace();
+            let keyword = parts.next().unwrap_or_default();
+            let variable = parts.next();
+            let condition = match (keyword, variable, parts.next()) {
+                ("if", Some(variable), None) => Condition::If(variable.to_string()),
+                ("unless", Some(variable), None) => Condition::Unless(variable.to_string()),
+                ("if" | "unless", _, _) => return Err(ForgeTreeError::parse(format!(
+                    "Block conditions take exactly one variable: {}", content
+                ))),
+                _ => return Ok(None),
+            };
+            return Ok(Some(ParsedLine::BlockStart(condition)));
+        }
+
+        // Any other `{{...}}` is a templated name
+        Ok(None)
+    }
+
+    /// Split trailing `[...]` annotations off an entry, e.g. `Dockerfile  [if docker]`
+    ///
+    /// Annotations must be separated from the name by whitespace so names like
+    /// `[id].tsx` are left alone. One bracket may hold several comma-separated
+    /// annotations.
+    fn split_annotations(&self, content: &str) -> (String, Vec<String>) {
+        let mut rest = content.trim_end();
+        let mut groups = Vec::new();
+
+        while rest.ends_with(']') {
+            let Some(open) = rest.rfind('[') else { break };
+            let before = &rest[..open];
+            if !before.ends_with(char::is_whitespace) || before.trim().is_empty() {
+                break;
+            }
+            groups.push(&rest[open + 1..rest.len() - 1]);
+            rest = before.trim_end();
+        }
+
+        let annotations = groups.into_iter()
+            .rev()
+            .flat_map(|group| group.split(','))
+            .map(|annotation| annotation.trim().to_string())
+            .filter(|annotation| !annotation.is_empty())
+            .collect();
+
+        (rest.to_string(), annotations)
+    }
+
+    fn apply_annotations(&self, item: &mut StructureItem, annotations: &[String], line_number: usize) -> Result<()> {
+        for annotation in annotations {
+            if let Some(mode) = annotation.strip_prefix("mode=") {
+                if matches!(item.item_type, ItemType::Symlink { .. }) {
+                    return Err(ForgeTreeError::parse_at(line_number, format!(
+                        "Symlinks have no mode of their own: [{}] at line {}", annotation, line_number
+                    )));
+                }
+                let mode = parse_mode(mode.trim()).ok_or_else(|| ForgeTreeError::parse_at(line_number, format!(
+                    "Invalid mode [{}] at line {}: expected octal permission bits like 755", annotation, line_number
+                )))?;
+                item.mode = Some(mode);
+                continue;
+            }
+
+            let (keyword, argument) = annotation.split_once(char::is_whitespace)
+                .map(|(keyword, argument)| (keyword, argument.trim()))
+                .unwrap_or((annotation.as_str(), ""));
+
+            let condition = match keyword {
+                "if" if !argument.is_empty() => Condition::If(argument.to_string()),
+                "unless" if !argument.is_empty() => Condition::Unless(argument.to_string()),
+                "each" if !argument.is_empty() => {
+                    if item.each.is_some() {
+                        return Err(ForgeTreeError::parse_at(line_number, format!(
+                            "Only one [each] annotation is allowed per entry (line {})", line_number
+                        )));
+                    }
+                    item.each = Some(argument.to_string());
+                    continue;
+                }
+                "file" if argument.is_empty() => {
+                    if matches!(item.item_type, ItemType::Symlink { .. }) {
+                        return Err(ForgeTreeError::parse_at(line_number, format!(
+                            "A symlink can't be marked [file] (line {})", line_number
+                        )));
+                    }
+                    item.item_type = ItemType::File;
+                    continue;
+                }
+                "replace" if argument.is_empty() => {
+                    if item.overlay.is_some() {
+                        return Err(ForgeTreeError::parse_at(line_number, format!(
+                            "An entry can't be both removed and replaced (line {})", line_number
+                        )));
+                    }
+                    item.overlay = Some(OverlayOp::Replace);
+                    continue;
+                }
+                _ => return Err(ForgeTreeError::parse_at(line_number, format!(
+                    "Unknown annotation [{}] at line {}", annotation, line_number
+                ))),
+            };
+
+            item.condition = Some(match item.condition.take() {
+                Some(existing) => existing.and(condition),
+                None => condition,
+            });
+        }
+
+        Ok(())
     }
 }
 
+/// A `{{#if}}`/`{{#unless}}` block that hasn't been closed yet
+struct OpenBlock {
+    keyword: &'static str,
+    condition: Condition,
+    line_number: usize,
+}
+
+/// A single line of the tree, classified
+enum ParsedLine {
+    Entry {
+        name: String,
+        kind: EntryKind,
+        annotations: Vec<String>,
+    },
+    Include {
+        target: String,
+        annotations: Vec<String>,
+    },
+    BlockStart(Condition),
+    BlockElse,
+    BlockEnd(&'static str),
+}
+
+/// What kind of item an entry line describes
+enum EntryKind {
+    /// `inferred` when the name has no trailing `/` and was taken for a directory
+    /// only because it has no extension
+    Directory { inferred: bool },
+    /// `executable` when marked with a trailing `*`
+    File { executable: bool },
+    Symlink { target: String },
+}
+
+/// An include error located at the outermost `@include` directive of `stack`
+fn include_error(stack: &[IncludeFrame], message: String) -> ForgeTreeError {
+    let outermost = stack.first();
+    ForgeTreeError::Include {
+        message,
+        file: outermost.map(|frame| frame.file.clone()),
+        line: outermost.map(|frame| frame.line),
+    }
+}
+
+/// Collapse `.` and `..` components without touching the filesystem
+fn normalize_path(path: &Path) -> PathBuf {
+    use std::path::Component;
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(normalized.components().next_back(), Some(Component::Normal(_))) => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Render an include stack as `a.tree:3 → b.tree:5`
+fn format_stack(stack: &[IncludeFrame]) -> String {
+    stack.iter()
+        .map(|frame| format!("{}:{}", frame.file.display(), frame.line))
+        .collect::<Vec<_>>()
+        .join(" → ")
+}
+
 impl Default for TreeParser {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,165 @@
+//! Rendering a ProjectStructure back into the tree text format
+//!
+//! The output uses the same box-drawing syntax the parser accepts, so parsing
+//! it back yields the same entries, kinds, modes and annotations. File
+//! contents and templates aren't part of the tree format and are left out.
+
+use crate::git::GitSettings;
+use crate::hooks::Hooks;
+use crate::parser::{ItemType, OverlayOp, ProjectStructure, StructureItem, EXECUTABLE_MODE, SYMLINK_ARROW};
+use crate::variables::VariableDecls;
+use crate::Result;
+use crate::ForgeTreeError;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize)]
+struct FrontMatter<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extends: Option<&'a str>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    variables: &'a VariableDecls,
+    #[serde(skip_serializing_if = "Hooks::is_empty")]
+    hooks: &'a Hooks,
+    #[serde(skip_serializing_if = "GitSettings::is_empty")]
+    git: &'a GitSettings,
+}
+
+pub struct TreeWriter {
+    front_matter: bool,
+}
+
+impl TreeWriter {
+    pub fn new() -> Self {
+        Self { front_matter: true }
+    }
+
+    /// Whether declarations are written as a YAML front matter block (default: true)
+    pub fn with_front_matter(mut self, front_matter: bool) -> Self {
+        self.front_matter = front_matter;
+        self
+    }
+
+    /// Render `structure` as tree text, with its declarations as front matter if enabled
+    pub fn write(&self, structure: &ProjectStructure) -> Result<String> {
+        let mut output = String::new();
+
+        let has_front_matter = !structure.declarations.is_empty()
+            || structure.extends.is_some()
+            || !structure.hooks.is_empty()
+            || !structure.git.is_empty();
+        if self.front_matter && has_front_matter {
+            let front_matter = FrontMatter {
+                extends: structure.extends.as_deref(),
+                variables: &structure.declarations,
+                hooks: &structure.hooks,
+                git: &structure.git,
+            };
+            let yaml = serde_yaml::to_string(&front_matter)
+                .map_err(|e| ForgeTreeError::parse(format!("Failed to write front matter: {}", e)))?;
+            output.push_str("---\n");
+            output.push_str(&yaml);
+            output.push_str("---\n");
+        }
+
+        output.push_str(&structure.root);
+        output.push_str("/\n");
+        self.write_items(&structure.items, "", &mut output);
+
+        Ok(output)
+    }
+
+    fn write_items(&self, items: &[StructureItem], prefix: &str, output: &mut String) {
+        for (index, item) in items.iter().enumerate() {
+            let is_last = index == items.len() - 1;
+            let connector = if is_last { "└── " } else { "├── " };
+
+            output.push_str(prefix);
+            output.push_str(connector);
+            output.push_str(&self.format_entry(item));
+            output.push('\n');
+
+            let child_prefix = format!("{}{}", prefix, if is_last { "    " } else { "│   " });
+            self.write_items(&item.children, &child_prefix, output);
+        }
+    }
+
+    /// Format a single entry, marking directories with a trailing slash, executables with `*`
+    /// and other extensionless files with `[file]`
+    fn format_entry(&self, item: &StructureItem) -> String {
+        let removed = if item.overlay == Some(OverlayOp::Remove) { "-" } else { "" };
+        let executable = matches!(item.item_type, ItemType::File) && item.mode == Some(EXECUTABLE_MODE);
+        let mut entry = match &item.item_type {
+            ItemType::Directory => format!("{}{}/", removed, item.name),
+            ItemType::File if executable => format!("{}{}*", removed, item.name),
+            ItemType::File => format!("{}{}", removed, item.name),
+            ItemType::Symlink { target } => format!("{}{}{}{}", removed, item.name, SYMLINK_ARROW, target),
+        };
+
+        let mut annotations = Vec::new();
+        if let Some(condition) = &item.condition {
+            annotations.push(condition.to_string());
+        }
+        if let Some(list) = &item.each {
+            annotations.push(format!("each {}", list));
+        }
+        if item.overlay == Some(OverlayOp::Replace) {
+            annotations.push("replace".to_string());
+        }
+        if let Some(mode) = item.mode.filter(|_| !executable) {
+            annotations.push(format!("mode={:o}", mode));
+        }
+        // Without an extension or annotation the parser would take a plain file for a directory
+        if annotations.is_empty() && item.item_type == ItemType::File && !executable && !item.name.contains('.') {
+            annotations.push("file".to_string());
+        }
+        if !annotations.is_empty() {
+            entry.push_str(&format!("  [{}]", annotations.join(", ")));
+        }
+
+        entry
+    }
+}
+
+impl Default for TreeWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    fn entries(structure: &ProjectStructure) -> Vec<(String, ItemType, Option<u32>)> {
+        structure.iter().map(|item| (item.path.clone(), item.item_type.clone(), item.mode)).collect()
+    }
+
+    #[test]
+    fn extensionless_files_round_trip_as_files() {
+        let structure = ProjectStructure::builder("app")
+            .file("LICENSE")
+            .file("Makefile")
+            .dir("vendor", |dir| dir)
+            .build()
+            .unwrap();
+
+        let written = TreeWriter::new().write(&structure).unwrap();
+        assert!(written.contains("LICENSE  [file]") && written.contains("vendor/\n"), "{}", written);
+
+        let reparsed = Parser::new().parse(&written).unwrap();
+        assert_eq!(entries(&reparsed), entries(&structure));
+        assert_eq!(reparsed.find("Makefile").unwrap().item_type, ItemType::File);
+    }
+
+    #[test]
+    fn annotated_and_executable_files_need_no_marker() {
+        let input = "app/\n├── Dockerfile  [if docker]\n├── run*\n└── README.md\n";
+        let structure = Parser::new().parse(input).unwrap();
+
+        let written = TreeWriter::new().write(&structure).unwrap();
+
+        assert_eq!(written, input);
+    }
+}
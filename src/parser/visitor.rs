@@ -0,0 +1,213 @@
+//! Visitor-based traversal of structure items
+
+use crate::parser::{ItemType, ProjectStructure, StructureItem};
+
+/// What a [`StructureVisitor`] callback wants the walk to do next
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Visit {
+    /// Keep walking
+    #[default]
+    Continue,
+    /// Don't descend into this directory; `leave_dir` is not called for it.
+    /// Same as `Continue` when returned from `visit_file` or `leave_dir`.
+    SkipChildren,
+    /// Stop the whole walk immediately
+    Stop,
+}
+
+/// Callbacks for [`walk`]
+///
+/// Every method has a default that does nothing, so implementors only
+/// override what they need. `depth` is 0 for top-level items and `path` is
+/// the item's `/`-separated location relative to the root, computed during
+/// the walk so it's correct even if `StructureItem::path` is stale.
+pub trait StructureVisitor {
+    fn enter_dir(&mut self, _item: &StructureItem, _depth: usize, _path: &str) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_dir(&mut self, _item: &StructureItem, _depth: usize, _path: &str) -> Visit {
+        Visit::Continue
+    }
+
+    /// Called for files and symlinks
+    fn visit_file(&mut self, _item: &StructureItem, _depth: usize, _path: &str) -> Visit {
+        Visit::Continue
+    }
+}
+
+/// Walk the items depth-first in tree order, calling the visitor for each
+///
+/// Returns [`Visit::Stop`] if a callback stopped the walk early, otherwise
+/// [`Visit::Continue`].
+pub fn walk<V: StructureVisitor + ?Sized>(items: &[StructureItem], visitor: &mut V) -> Visit {
+    walk_level(items, 0, "", visitor)
+}
+
+fn walk_level<V: StructureVisitor + ?Sized>(
+    items: &[StructureItem],
+    depth: usize,
+    prefix: &str,
+    visitor: &mut V,
+) -> Visit {
+    for item in items {
+        let path = if prefix.is_empty() {
+            item.name.clone()
+        } else {
+            format!("{}/{}", prefix, item.name)
+        };
+
+        let flow = match item.item_type {
+            ItemType::File | ItemType::Symlink { .. } => visitor.visit_file(item, depth, &path),
+            ItemType::Directory => match visitor.enter_dir(item, depth, &path) {
+                Visit::Continue => match walk_level(&item.children, depth + 1, &path, visitor) {
+                    Visit::Stop => Visit::Stop,
+                    _ => visitor.leave_dir(item, depth, &path),
+                },
+                other => other,
+            },
+        };
+
+        if flow == Visit::Stop {
+            return Visit::Stop;
+        }
+    }
+
+    Visit::Continue
+}
+
+/// Directory and file counts for a tree, gathered with [`walk`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StructureStats {
+    pub directories: usize,
+    pub files: usize,
+    /// Deepest nesting level seen (0 when everything is top-level)
+    pub max_depth: usize,
+}
+
+impl StructureStats {
+    /// Count every item in `items` and their descendants
+    pub fn of(items: &[StructureItem]) -> Self {
+        let mut stats = Self::default();
+        walk(items, &mut stats);
+        stats
+    }
+
+    /// Total number of directories and files
+    pub fn total(&self) -> usize {
+        self.directories + self.files
+    }
+}
+
+impl StructureVisitor for StructureStats {
+    fn enter_dir(&mut self, _item: &StructureItem, depth: usize, _path: &str) -> Visit {
+        self.directories += 1;
+        self.max_depth = self.max_depth.max(depth);
+        Visit::Continue
+    }
+
+    fn visit_file(&mut self, _item: &StructureItem, depth: usize, _path: &str) -> Visit {
+        self.files += 1;
+        self.max_depth = self.max_depth.max(depth);
+        Visit::Continue
+    }
+}
+
+impl ProjectStructure {
+    /// Walk the whole structure with a visitor, see [`walk`]
+    pub fn accept<V: StructureVisitor + ?Sized>(&self, visitor: &mut V) -> Visit {
+        walk(&self.items, visitor)
+    }
+
+    /// Directory and file counts for the whole structure
+    pub fn stats(&self) -> StructureStats {
+        StructureStats::of(&self.items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn structure() -> ProjectStructure {
+        ProjectStructure::builder("app")
+            .dir("src", |d| d.dir("bin", |d| d.file("cli.rs")).file("lib.rs"))
+            .dir("target", |d| d.file("build.log"))
+            .file("README.md")
+            .build()
+            .unwrap()
+    }
+
+    /// Records every callback, skipping `target` and stopping at `stop_at`
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+        stop_at: Option<&'static str>,
+    }
+
+    impl StructureVisitor for Recorder {
+        fn enter_dir(&mut self, _item: &StructureItem, depth: usize, path: &str) -> Visit {
+            self.events.push(format!("enter {} {}", depth, path));
+            if path == "target" { Visit::SkipChildren } else { Visit::Continue }
+        }
+
+        fn leave_dir(&mut self, _item: &StructureItem, depth: usize, path: &str) -> Visit {
+            self.events.push(format!("leave {} {}", depth, path));
+            Visit::Continue
+        }
+
+        fn visit_file(&mut self, _item: &StructureItem, depth: usize, path: &str) -> Visit {
+            self.events.push(format!("file {} {}", depth, path));
+            if self.stop_at == Some(path) { Visit::Stop } else { Visit::Continue }
+        }
+    }
+
+    #[test]
+    fn calls_back_in_tree_order_and_skips_children() {
+        let mut recorder = Recorder::default();
+
+        assert_eq!(structure().accept(&mut recorder), Visit::Continue);
+        assert_eq!(
+            recorder.events,
+            [
+                "enter 0 src",
+                "enter 1 src/bin",
+                "file 2 src/bin/cli.rs",
+                "leave 1 src/bin",
+                "file 1 src/lib.rs",
+                "leave 0 src",
+                "enter 0 target",
+                "file 0 README.md",
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_without_leaving_open_directories() {
+        let mut recorder = Recorder { stop_at: Some("src/bin/cli.rs"), ..Recorder::default() };
+
+        assert_eq!(structure().accept(&mut recorder), Visit::Stop);
+        assert_eq!(recorder.events.last().unwrap(), "file 2 src/bin/cli.rs");
+        assert!(!recorder.events.iter().any(|event| event.starts_with("leave")));
+    }
+
+    #[test]
+    fn paths_are_computed_during_the_walk() {
+        let mut structure = structure();
+        structure.get_mut("src").unwrap().name = "source".to_string();
+        let mut recorder = Recorder::default();
+
+        structure.accept(&mut recorder);
+
+        assert!(recorder.events.contains(&"file 1 source/lib.rs".to_string()), "{:?}", recorder.events);
+    }
+
+    #[test]
+    fn counts_directories_files_and_depth() {
+        let stats = structure().stats();
+
+        assert_eq!(stats, StructureStats { directories: 3, files: 4, max_depth: 2 });
+        assert_eq!(stats.total(), 7);
+        assert_eq!(StructureStats::of(&[]), StructureStats::default());
+    }
+}
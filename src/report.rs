@@ -0,0 +1,321 @@
+//! Machine-readable reports printed by `--format json`
+//!
+//! The documents are versioned with [`REPORT_VERSION`]; fields are only ever
+//! added within a version. Paths always use `/` separators.
+
+use crate::diff::Difference;
+use crate::generator::{FileOutcome, GenerationSummary};
+use crate::lockfile::slash_path;
+use crate::parser::{Duplicate, DuplicatePolicy, ProjectStructure, StructureStats};
+use crate::{ForgeTreeError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Version of the report format
+pub const REPORT_VERSION: u32 = 1;
+
+/// An error or warning, with its location when known
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Problem {
+    /// Stable identifier such as `parse` or `duplicate_entry`
+    pub kind: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+}
+
+impl Problem {
+    /// Located in the file the error names (e.g. an `extends` base), or else in `file`
+    pub fn from_error(error: &ForgeTreeError, file: &str) -> Self {
+        Self {
+            kind: error.kind().to_string(),
+            message: error.to_string(),
+            file: Some(error.file().map_or_else(|| file.to_string(), |path| path.display().to_string())),
+            line: error.line(),
+        }
+    }
+
+    /// A duplicate sibling, located at its second occurrence
+    pub fn duplicate(duplicate: &Duplicate, file: &str) -> Self {
+        Self {
+            kind: "duplicate_entry".to_string(),
+            message: format!("Duplicate entry {}", duplicate),
+            file: Some(file.to_string()),
+            line: duplicate.second_line,
+        }
+    }
+}
+
+/// Number of items in a structure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemCounts {
+    pub directories: usize,
+    pub files: usize,
+    pub total: usize,
+}
+
+impl From<StructureStats> for ItemCounts {
+    fn from(stats: StructureStats) -> Self {
+        Self {
+            directories: stats.directories,
+            files: stats.files,
+            total: stats.total(),
+        }
+    }
+}
+
+/// Result of `forge-tree validate --format json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidateReport {
+    pub version: u32,
+    pub command: String,
+    pub file: String,
+    pub valid: bool,
+    /// The parsed structure after includes, `extends` and duplicate resolution
+    pub structure: Option<ProjectStructure>,
+    pub counts: Option<ItemCounts>,
+    pub warnings: Vec<Problem>,
+    pub errors: Vec<Problem>,
+}
+
+impl ValidateReport {
+    pub fn new(
+        file: &str,
+        duplicates: &[Duplicate],
+        policy: DuplicatePolicy,
+        result: &Result<ProjectStructure>,
+    ) -> Self {
+        let duplicate_problems = duplicates.iter().map(|duplicate| Problem::duplicate(duplicate, file));
+        let (mut errors, warnings) = if policy == DuplicatePolicy::Error {
+            (duplicate_problems.collect(), Vec::new())
+        } else {
+            (Vec::new(), duplicate_problems.collect())
+        };
+
+        // Under the error policy the duplicates themselves are the errors
+        if let Err(e) = result {
+            if errors.is_empty() {
+                errors.push(Problem::from_error(e, file));
+            }
+        }
+
+        let structure = result.as_ref().ok().cloned();
+        Self {
+            version: REPORT_VERSION,
+            command: "validate".to_string(),
+            file: file.to_string(),
+            valid: result.is_ok(),
+            counts: structure.as_ref().map(|structure| structure.stats().into()),
+            structure,
+            warnings,
+            errors,
+        }
+    }
+}
+
+/// What a forge run did, by category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForgeCounts {
+    pub directories: usize,
+    pub created: usize,
+    pub skipped: usize,
+    pub overwritten: usize,
+    pub backed_up: usize,
+    pub excluded: usize,
+}
+
+/// A file that existed and was moved aside before being replaced
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupRecord {
+    pub path: String,
+    pub backup: String,
+}
+
+/// Result of `forge-tree forge --format json`
+///
+/// File paths are relative to `output` and start with the project root.
+/// In a dry run, `created` lists the files that would be created.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForgeReport {
+    pub version: u32,
+    pub command: String,
+    pub file: String,
+    pub output: String,
+    pub dry_run: bool,
+    pub success: bool,
+    pub counts: Option<ForgeCounts>,
+    pub created: Vec<String>,
+    pub skipped: Vec<String>,
+    pub overwritten: Vec<String>,
+    pub backed_up: Vec<BackupRecord>,
+    pub errors: Vec<Problem>,
+}
+
+impl ForgeReport {
+    pub fn new(file: &str, output: &str, dry_run: bool, result: &Result<GenerationSummary>) -> Self {
+        let mut report = Self {
+            version: REPORT_VERSION,
+            command: "forge".to_string(),
+            file: file.to_string(),
+            output: output.to_string(),
+            dry_run,
+            success: result.is_ok(),
+            counts: None,
+            created: Vec::new(),
+            skipped: Vec::new(),
+            overwritten: Vec::new(),
+            backed_up: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        let summary = match result {
+            Ok(summary) => summary,
+            Err(e) => {
+                report.errors.push(Problem::from_error(e, file));
+                return report;
+            }
+        };
+
+        report.counts = Some(ForgeCounts {
+            directories: summary.directories,
+            created: summary.created,
+            skipped: summary.skipped,
+            overwritten: summary.overwritten,
+            backed_up: summary.backed_up,
+            excluded: summary.excluded,
+        });
+        for (path, outcome) in &summary.files {
+            let path = slash_path(path);
+            match outcome {
+                FileOutcome::Created => report.created.push(path),
+                FileOutcome::Skipped => report.skipped.push(path),
+                FileOutcome::Overwritten => report.overwritten.push(path),
+                FileOutcome::BackedUp(backup) => report.backed_up.push(BackupRecord {
+                    path,
+                    backup: slash_path(backup),
+                }),
+            }
+        }
+
+        report
+    }
+}
+
+/// Result of `forge-tree diff --format json` and `forge-tree check --format json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffReport {
+    pub version: u32,
+    pub command: String,
+    pub file: String,
+    pub directory: String,
+    /// True when the directory matches the structure
+    pub matches: bool,
+    pub differences: Vec<Difference>,
+    pub errors: Vec<Problem>,
+}
+
+impl DiffReport {
+    pub fn new(command: &str, file: &str, directory: &str, result: &Result<Vec<Difference>>) -> Self {
+        let (differences, errors) = match result {
+            Ok(differences) => (differences.clone(), Vec::new()),
+            Err(e) => (Vec::new(), vec![Problem::from_error(e, file)]),
+        };
+        Self {
+            version: REPORT_VERSION,
+            command: command.to_string(),
+            file: file.to_string(),
+            directory: directory.to_string(),
+            matches: result.as_ref().is_ok_and(|differences| differences.is_empty()),
+            differences,
+            errors,
+        }
+    }
+}
+
+/// Print a report as pretty JSON on stdout
+pub fn print<T: Serialize>(report: &T) -> Result<()> {
+    let json = serde_json::to_string_pretty(report)
+        .map_err(|e| ForgeTreeError::Io(std::io::Error::other(e)))?;
+    println!("{}", json);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::path::PathBuf;
+
+    fn to_json<T: Serialize>(report: &T) -> Value {
+        serde_json::to_value(report).unwrap()
+    }
+
+    fn duplicate() -> Duplicate {
+        Duplicate { path: "src".to_string(), first_line: Some(2), second_line: Some(5) }
+    }
+
+    #[test]
+    fn validate_reports_duplicates_as_errors_under_the_error_policy() {
+        let result = Err(ForgeTreeError::DuplicateEntry(duplicate().to_string()));
+
+        let report = to_json(&ValidateReport::new("app.tree", &[duplicate()], DuplicatePolicy::Error, &result));
+
+        assert_eq!(report["version"], REPORT_VERSION);
+        assert_eq!(report["command"], "validate");
+        assert_eq!(report["valid"], false);
+        assert_eq!(report["structure"], Value::Null);
+        assert_eq!(report["warnings"], json!([]));
+        assert_eq!(
+            report["errors"],
+            json!([{ "kind": "duplicate_entry", "message": "Duplicate entry 'src' at lines 2 and 5", "file": "app.tree", "line": 5 }])
+        );
+    }
+
+    #[test]
+    fn validate_reports_resolved_duplicates_as_warnings() {
+        let result = ProjectStructure::builder("app").dir("src", |d| d.file("main.rs")).build();
+
+        let report = to_json(&ValidateReport::new("app.tree", &[duplicate()], DuplicatePolicy::Merge, &result));
+
+        assert_eq!(report["valid"], true);
+        assert_eq!(report["counts"], json!({ "directories": 1, "files": 1, "total": 2 }));
+        assert_eq!(report["warnings"][0]["kind"], "duplicate_entry");
+        assert_eq!(report["errors"], json!([]));
+    }
+
+    #[test]
+    fn forge_lists_files_by_outcome() {
+        let summary = GenerationSummary {
+            directories: 1,
+            created: 1,
+            backed_up: 1,
+            files: vec![
+                (PathBuf::from("app/src/main.rs"), FileOutcome::Created),
+                (PathBuf::from("app/README.md"), FileOutcome::BackedUp(PathBuf::from("app/README.md.orig"))),
+            ],
+            ..GenerationSummary::default()
+        };
+
+        let report = to_json(&ForgeReport::new("app.tree", ".", false, &Ok(summary)));
+
+        assert_eq!(report["success"], true);
+        assert_eq!(report["counts"]["created"], 1);
+        assert_eq!(report["created"], json!(["app/src/main.rs"]));
+        assert_eq!(report["backed_up"], json!([{ "path": "app/README.md", "backup": "app/README.md.orig" }]));
+    }
+
+    #[test]
+    fn errors_name_the_file_they_point_at() {
+        let error = ForgeTreeError::Parse { message: "bad".into(), file: Some(PathBuf::from("base.tree")), line: Some(3) };
+
+        let report = to_json(&ForgeReport::new("app.tree", ".", true, &Err(error)));
+
+        assert_eq!(report["success"], false);
+        assert_eq!(report["counts"], Value::Null);
+        assert_eq!(
+            report["errors"],
+            json!([{ "kind": "parse", "message": "Parse error: bad", "file": "base.tree", "line": 3 }])
+        );
+    }
+}
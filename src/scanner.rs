@@ -0,0 +1,188 @@
+//! Reading an existing directory back into a ProjectStructure
+
+use crate::lockfile::{slash_path, LOCKFILE_NAME};
+use crate::parser::{ProjectStructure, StructureItem};
+use crate::{ForgeTreeError, Result};
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// Entries that are never part of a scanned structure
+const DEFAULT_IGNORES: &[&str] = &[".git", LOCKFILE_NAME];
+
+/// Glob patterns for paths to leave out
+///
+/// A pattern without `/` matches any entry with that name (`target`,
+/// `*.log`); a pattern with `/` matches the whole root-relative path
+/// (`docs/*.md`). `*` and `?` stay within one path segment, `**` crosses
+/// segments. Ignoring a directory ignores everything below it.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreSet {
+    names: Vec<Regex>,
+    paths: Vec<Regex>,
+}
+
+impl IgnoreSet {
+    pub fn new<I, S>(patterns: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut set = Self::default();
+        for pattern in patterns {
+            let pattern = pattern.as_ref().trim_end_matches('/');
+            let regex = glob_regex(pattern)?;
+            if pattern.contains('/') {
+                set.paths.push(regex);
+            } else {
+                set.names.push(regex);
+            }
+        }
+        Ok(set)
+    }
+
+    /// Whether a `/`-separated root-relative path, or any of its ancestors, is ignored
+    pub fn is_ignored(&self, path: &str) -> bool {
+        let mut prefix = String::new();
+        for segment in path.split('/') {
+            if !prefix.is_empty() {
+                prefix.push('/');
+            }
+            prefix.push_str(segment);
+
+            if self.names.iter().any(|name| name.is_match(segment))
+                || self.paths.iter().any(|full| full.is_match(&prefix))
+            {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Builds a [`ProjectStructure`] from a directory on disk
+///
+/// Entries are sorted by name so scans are deterministic. `.git` and
+/// `.forge-tree.lock` are always skipped. Symlinks are recorded with their
+/// target and never followed. On Unix every other entry's permission bits are
+/// recorded in its `mode`.
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    ignore: IgnoreSet,
+}
+
+impl Scanner {
+    pub fn new() -> Self {
+        Self {
+            ignore: IgnoreSet::new(DEFAULT_IGNORES).expect("default ignore patterns are valid"),
+        }
+    }
+
+    /// Skip entries matching these glob patterns, in addition to the defaults
+    pub fn with_ignore<I, S>(mut self, patterns: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let extra = IgnoreSet::new(patterns)?;
+        self.ignore.names.extend(extra.names);
+        self.ignore.paths.extend(extra.paths);
+        Ok(self)
+    }
+
+    pub fn ignore(&self) -> &IgnoreSet {
+        &self.ignore
+    }
+
+    /// Scan `dir`; the structure's root is the directory's own name
+    pub fn scan<P: AsRef<Path>>(&self, dir: P) -> Result<ProjectStructure> {
+        let dir = dir.as_ref();
+        if !dir.is_dir() {
+            return Err(ForgeTreeError::InvalidPath(format!("Not a directory: {}", dir.display())));
+        }
+
+        let root = dir.canonicalize()
+            .ok()
+            .and_then(|dir| dir.file_name().map(|name| name.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| dir.display().to_string());
+        let mut structure = ProjectStructure::builder(&root).build()?;
+
+        let walker = WalkDir::new(dir)
+            .min_depth(1)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| {
+                let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+                !self.ignore.is_ignored(&slash_path(relative))
+            });
+
+        for entry in walker {
+            let entry = entry.map_err(|e| ForgeTreeError::Io(e.into()))?;
+            let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+            let name = entry.file_name().to_string_lossy().into_owned();
+
+            let mut item = if entry.file_type().is_symlink() {
+                let target = fs::read_link(entry.path())?;
+                StructureItem::symlink(&name, &target.to_string_lossy())
+            } else if entry.file_type().is_dir() {
+                StructureItem::directory(&name)
+            } else {
+                StructureItem::file(&name)
+            };
+            if !entry.file_type().is_symlink() {
+                item.mode = mode_of(&entry)?;
+            }
+
+            // Parents are always visited before their children
+            let parent = relative.parent().map(slash_path).unwrap_or_default();
+            if parent.is_empty() {
+                structure.items.push(item);
+            } else if let Some(parent) = structure.get_mut(&parent) {
+                parent.children.push(item);
+            }
+        }
+
+        structure.recompute_paths();
+        Ok(structure)
+    }
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Permission bits of a scanned entry; `None` where there are none (not on Unix)
+#[cfg(unix)]
+fn mode_of(entry: &walkdir::DirEntry) -> Result<Option<u32>> {
+    use std::os::unix::fs::PermissionsExt;
+    let metadata = entry.metadata().map_err(|e| ForgeTreeError::Io(e.into()))?;
+    Ok(Some(metadata.permissions().mode() & 0o7777))
+}
+
+#[cfg(not(unix))]
+fn mode_of(_entry: &walkdir::DirEntry) -> Result<Option<u32>> {
+    Ok(None)
+}
+
+/// Translate a glob into an anchored regex
+fn glob_regex(pattern: &str) -> Result<Regex> {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+
+    Regex::new(&regex).map_err(|e| ForgeTreeError::parse(format!("Invalid ignore pattern '{}': {}", pattern, e)))
+}
@@ -0,0 +1,185 @@
+//! Reverting a forge run using its `.forge-tree.lock` manifest
+
+use crate::lockfile::{hash_bytes, project_path, LockEntryKind, LockEntryStatus, Lockfile, LOCKFILE_NAME};
+use crate::Result;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What `Undo::run` did (or would do, in a dry run)
+///
+/// All paths are relative to the project root, with `/` separators.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UndoReport {
+    /// Created files and symlinks that were deleted
+    pub removed_files: Vec<String>,
+    /// Files whose backup was moved back into place
+    pub restored: Vec<String>,
+    /// Created directories that were deleted because they ended up empty
+    pub removed_directories: Vec<String>,
+    /// Files changed (or symlinks retargeted) since the forge, left alone
+    pub modified: Vec<String>,
+    /// Files from the manifest that no longer exist
+    pub missing: Vec<String>,
+    /// Created directories that still contain other files
+    pub kept_directories: Vec<String>,
+    /// Whether the project root itself was deleted
+    pub removed_root: bool,
+}
+
+/// Removes exactly what a forge run created
+///
+/// Only files that forge created and that are unmodified (their hash still
+/// matches the manifest) are deleted; files that were backed up are restored
+/// from the backup. Directories forge created are removed once empty. The
+/// manifest itself is deleted when nothing was refused, so a later
+/// `--force` run can still use it.
+#[derive(Debug, Clone, Default)]
+pub struct Undo {
+    dry_run: bool,
+    force: bool,
+}
+
+impl Undo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report what would be removed without touching anything
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Also remove (or restore over) files that were modified since the forge
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Undo the forge recorded in `project_root/.forge-tree.lock`
+    pub fn run<P: AsRef<Path>>(&self, project_root: P) -> Result<UndoReport> {
+        let project_root = project_root.as_ref();
+        let lockfile = Lockfile::load(project_root)?;
+        let mut report = UndoReport::default();
+
+        // Paths that are gone (or would be) once this run finishes
+        let mut removed: HashSet<PathBuf> = HashSet::new();
+
+        // Children before parents, so directories can be checked for emptiness
+        for entry in lockfile.entries.iter().rev() {
+            let path = project_path(project_root, &entry.path)?;
+
+            match (entry.kind, entry.status) {
+                (LockEntryKind::File, LockEntryStatus::Created | LockEntryStatus::BackedUp) => {
+                    if !path.is_file() {
+                        report.missing.push(entry.path.clone());
+                        continue;
+                    }
+                    if !self.force && entry.hash.as_deref() != Some(hash_bytes(&fs::read(&path)?).as_str()) {
+                        report.modified.push(entry.path.clone());
+                        continue;
+                    }
+
+                    let backup = entry.backup.as_deref()
+                        .map(|backup| project_path(project_root, backup))
+                        .transpose()?
+                        .filter(|backup| backup.is_file());
+                    match backup {
+                        Some(backup) => {
+                            if !self.dry_run {
+                                fs::rename(&backup, &path)?;
+                            }
+                            removed.insert(backup);
+                            report.restored.push(entry.path.clone());
+                        }
+                        None if entry.status == LockEntryStatus::Created => {
+                            if !self.dry_run {
+                                fs::remove_file(&path)?;
+                            }
+                            removed.insert(path);
+                            report.removed_files.push(entry.path.clone());
+                        }
+                        None => report.missing.push(entry.backup.clone().unwrap_or_default()),
+                    }
+                }
+                (LockEntryKind::Symlink, LockEntryStatus::Created | LockEntryStatus::BackedUp) => {
+                    let Ok(current) = fs::read_link(&path) else {
+                        report.missing.push(entry.path.clone());
+                        continue;
+                    };
+                    if !self.force && entry.target.as_deref().map(Path::new) != Some(current.as_path()) {
+                        report.modified.push(entry.path.clone());
+                        continue;
+                    }
+
+                    // The link itself is replaced, so whatever was backed up can be moved straight back
+                    let backup = entry.backup.as_deref()
+                        .map(|backup| project_path(project_root, backup))
+                        .transpose()?
+                        .filter(|backup| fs::symlink_metadata(backup).is_ok());
+                    match backup {
+                        Some(backup) => {
+                            if !self.dry_run {
+                                fs::remove_file(&path)?;
+                                fs::rename(&backup, &path)?;
+                            }
+                            removed.insert(backup);
+                            report.restored.push(entry.path.clone());
+                        }
+                        None if entry.status == LockEntryStatus::Created => {
+                            if !self.dry_run {
+                                fs::remove_file(&path)?;
+                            }
+                            removed.insert(path);
+                            report.removed_files.push(entry.path.clone());
+                        }
+                        None => report.missing.push(entry.backup.clone().unwrap_or_default()),
+                    }
+                }
+                (LockEntryKind::Directory, LockEntryStatus::Created) => {
+                    if !path.is_dir() {
+                        continue;
+                    }
+                    if self.remove_if_empty(&path, &mut removed)? {
+                        report.removed_directories.push(entry.path.clone());
+                    } else {
+                        report.kept_directories.push(entry.path.clone());
+                    }
+                }
+                // Files and directories that existed before the forge are left alone
+                _ => {}
+            }
+        }
+
+        // Keep the manifest while something was refused, so `--force` can follow up
+        if report.modified.is_empty() {
+            let lock_path = project_root.join(LOCKFILE_NAME);
+            if !self.dry_run {
+                fs::remove_file(&lock_path)?;
+            }
+            removed.insert(lock_path);
+
+            if lockfile.root_created {
+                report.removed_root = self.remove_if_empty(project_root, &mut removed)?;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Remove a directory if everything in it has been (or would be) removed
+    fn remove_if_empty(&self, dir: &Path, removed: &mut HashSet<PathBuf>) -> Result<bool> {
+        for child in fs::read_dir(dir)? {
+            if !removed.contains(&child?.path()) {
+                return Ok(false);
+            }
+        }
+
+        if !self.dry_run {
+            fs::remove_dir(dir)?;
+        }
+        removed.insert(dir.to_path_buf());
+        Ok(true)
+    }
+}
@@ -0,0 +1,393 @@
+//! Bringing a forged project up to date with a changed structure
+//!
+//! The manifest keeps what was rendered last time, which serves as the
+//! common ancestor for a three-way merge between that output, the freshly
+//! rendered output and the files as they are now.
+
+use crate::generator::{FsBackend, OutputBackend};
+use crate::lockfile::{
+    hash_bytes, native_path, project_path, slash_path, LockEntry, LockEntryKind, LockEntryStatus, LockSource, Lockfile,
+    LOCKFILE_VERSION,
+};
+use crate::parser::ProjectStructure;
+use crate::{ForgeTreeError, Generator, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// How conflicting changes are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictStyle {
+    /// Write the merge result with `<<<<<<<`/`=======`/`>>>>>>>` markers into the file
+    #[default]
+    Markers,
+    /// Leave the file alone and write the upstream change as a patch to `file.rej`
+    Reject,
+}
+
+impl FromStr for ConflictStyle {
+    type Err = ForgeTreeError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "markers" => Ok(Self::Markers),
+            "reject" => Ok(Self::Reject),
+            other => Err(ForgeTreeError::parse(format!(
+                "Unknown conflict style: {} (expected markers or reject)", other
+            ))),
+        }
+    }
+}
+
+/// What `Update::run` did (or would do, in a dry run)
+///
+/// All paths are relative to the project root, with `/` separators.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpdateReport {
+    /// New files from the structure
+    pub created: Vec<String>,
+    /// Files that were unmodified locally and were replaced by the new version
+    pub updated: Vec<String>,
+    /// Files where local and upstream changes merged cleanly
+    pub merged: Vec<String>,
+    /// Files where local and upstream changes overlap
+    pub conflicts: Vec<String>,
+    /// Files dropped from the structure and unmodified locally, now deleted
+    pub removed: Vec<String>,
+    /// Upstream changes not applied because the file was deleted, isn't
+    /// forge's (it was kept when forging, or was already there) or, for
+    /// files dropped from the structure, was modified locally
+    pub kept: Vec<String>,
+}
+
+impl UpdateReport {
+    /// Whether anything changed (or would change)
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty()
+            && self.updated.is_empty()
+            && self.merged.is_empty()
+            && self.conflicts.is_empty()
+            && self.removed.is_empty()
+    }
+}
+
+/// Re-renders a structure with the recorded variables and merges the result
+/// into an existing project
+#[derive(Debug, Clone, Default)]
+pub struct Update {
+    dry_run: bool,
+    conflict_style: ConflictStyle,
+    source: Option<PathBuf>,
+}
+
+impl Update {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report what would change without writing anything
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn with_conflict_style(mut self, style: ConflictStyle) -> Self {
+        self.conflict_style = style;
+        self
+    }
+
+    /// Record the structure file (path and hash) in the updated manifest
+    pub fn with_source<P: Into<PathBuf>>(mut self, source: P) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Update the project at `project_root` to what `structure` renders to now
+    ///
+    /// Variables recorded in the manifest fill in anything the structure
+    /// doesn't set, so values given on the command line win.
+    pub fn run<P: AsRef<Path>>(
+        &self,
+        generator: &Generator,
+        structure: &ProjectStructure,
+        project_root: P,
+    ) -> Result<UpdateReport> {
+        let project_root = project_root.as_ref();
+        let lockfile = Lockfile::load(project_root)?;
+
+        let mut structure = structure.clone();
+        for (name, value) in &lockfile.variables {
+            structure.variables.entry(name.clone()).or_insert_with(|| value.clone());
+        }
+        let rendered = generator.render(&structure)?;
+
+        let mut report = UpdateReport::default();
+        let mut entries = Vec::new();
+
+        for directory in &rendered.directories {
+            let path = slash_path(directory);
+            let full_path = project_path(project_root, &path)?;
+            let status = match lockfile.entry(&path) {
+                Some(old) => old.status,
+                None if full_path.is_dir() => LockEntryStatus::Existed,
+                None => LockEntryStatus::Created,
+            };
+            if !full_path.is_dir() && !self.dry_run {
+                fs::create_dir_all(&full_path)?;
+            }
+            entries.push(LockEntry::directory(path, status));
+        }
+
+        for file in &rendered.files {
+            let path = slash_path(&file.path);
+            let new = file.contents.as_slice();
+            let old = lockfile.entry(&path);
+            let full_path = project_path(project_root, &path)?;
+            let current = read_bytes(&full_path)?;
+
+            let base = old.and_then(|old| old.content.as_deref());
+            let written = self.update_file(&full_path, &path, base, current.as_deref(), new, &mut report)?;
+            if let Some(mode) = file.mode.filter(|_| !self.dry_run && full_path.is_file()) {
+                FsBackend::new(project_root).set_permissions(&file.path, mode)?;
+            }
+
+            let entry = match old {
+                // The file now holds what the structure renders, which becomes the next merge base;
+                // keep how it first came to be, so `undo` stays accurate
+                Some(old) if base.is_some() && (written || current.as_deref() == Some(new)) => {
+                    let mut entry = LockEntry::file(path, old.status, new);
+                    entry.backup = old.backup.clone();
+                    entry
+                }
+                // Not written (kept, rejected or unchanged upstream): the recorded base still applies
+                Some(old) if !written => old.clone(),
+                None if !written && current.is_some() => LockEntry::file(path, LockEntryStatus::Skipped, new),
+                _ => LockEntry::file(path, LockEntryStatus::Created, new),
+            };
+            entries.push(entry);
+        }
+
+        for link in &rendered.symlinks {
+            let path = slash_path(&link.path);
+            let old = lockfile.entry(&path);
+            let base = old.and_then(|old| old.target.as_deref());
+            let written = self.update_symlink(project_root, &path, base, &link.target, &mut report)?;
+
+            let entry = match old {
+                // Forge's link: the structure's target is the base for the next update
+                Some(old) if base.is_some() => {
+                    let mut entry = LockEntry::symlink(path, old.status, &link.target);
+                    entry.backup = old.backup.clone();
+                    entry
+                }
+                // Still the user's link, as it was when forged
+                Some(old) if !written => old.clone(),
+                None if !written => LockEntry::symlink(path, LockEntryStatus::Skipped, &link.target),
+                _ => LockEntry::symlink(path, LockEntryStatus::Created, &link.target),
+            };
+            entries.push(entry);
+        }
+
+        self.remove_dropped(project_root, &lockfile, &entries, &mut report)?;
+
+        if !self.dry_run {
+            let source = match &self.source {
+                Some(source) => Some(LockSource::from_file(source)?),
+                None => lockfile.source.clone(),
+            };
+            Lockfile {
+                version: LOCKFILE_VERSION,
+                tool_version: env!("CARGO_PKG_VERSION").to_string(),
+                source,
+                variables: rendered.variables,
+                root: lockfile.root.clone(),
+                root_created: lockfile.root_created,
+                entries,
+            }.save(project_root)?;
+        }
+
+        Ok(report)
+    }
+
+    /// Apply one file's change, given its last rendered, current and new contents
+    ///
+    /// Returns whether the file was (or, in a dry run, would be) written.
+    /// Files without a last rendered version were never written by forge,
+    /// so they are only created where nothing exists yet. Contents are
+    /// compared as bytes; only text can be merged.
+    fn update_file(
+        &self,
+        full_path: &Path,
+        path: &str,
+        base: Option<&str>,
+        current: Option<&[u8]>,
+        new: &[u8],
+        report: &mut UpdateReport,
+    ) -> Result<bool> {
+        // Already up to date, or nothing changed upstream: keep whatever is there
+        if current == Some(new) || base.map(str::as_bytes) == Some(new) {
+            return Ok(false);
+        }
+
+        let (base, current) = match (base, current) {
+            (None, None) => {
+                self.write(full_path, new)?;
+                report.created.push(path.to_string());
+                return Ok(true);
+            }
+            // Deleted locally, or never forge's to change
+            (Some(_), None) | (None, Some(_)) => {
+                report.kept.push(path.to_string());
+                return Ok(false);
+            }
+            (Some(base), Some(current)) => (base, current),
+        };
+
+        if base.as_bytes() == current {
+            self.write(full_path, new)?;
+            report.updated.push(path.to_string());
+            return Ok(true);
+        }
+
+        // Changed on both sides, but not as text: leave the local file as it is
+        let (Ok(current), Ok(new)) = (std::str::from_utf8(current), std::str::from_utf8(new)) else {
+            report.conflicts.push(path.to_string());
+            return Ok(false);
+        };
+
+        match diffy::merge(base, current, new) {
+            Ok(merged) => {
+                self.write(full_path, merged.as_bytes())?;
+                report.merged.push(path.to_string());
+                Ok(true)
+            }
+            Err(with_markers) => {
+                report.conflicts.push(path.to_string());
+                match self.conflict_style {
+                    ConflictStyle::Markers => {
+                        self.write(full_path, with_markers.as_bytes())?;
+                        Ok(true)
+                    }
+                    ConflictStyle::Reject => {
+                        let patch = diffy::create_patch(base, new);
+                        let mut reject = full_path.as_os_str().to_owned();
+                        reject.push(".rej");
+                        self.write(Path::new(&reject), patch.to_string().as_bytes())?;
+                        Ok(false)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Point a symlink at its new target, unless it was changed or removed locally
+    ///
+    /// Returns whether the link was (or in a dry run, would be) written.
+    fn update_symlink(
+        &self,
+        project_root: &Path,
+        path: &str,
+        base: Option<&str>,
+        new: &Path,
+        report: &mut UpdateReport,
+    ) -> Result<bool> {
+        let full_path = project_path(project_root, path)?;
+        let current = fs::read_link(&full_path).ok();
+        if current.as_deref() == Some(new) || base.map(Path::new) == Some(new) {
+            return Ok(false);
+        }
+
+        let exists = fs::symlink_metadata(&full_path).is_ok();
+        let list = match (&current, base) {
+            // Never forged, and nothing in the way
+            (None, None) if !exists => &mut report.created,
+            // Unchanged since the last forge
+            (Some(current), Some(base)) if current == Path::new(base) => &mut report.updated,
+            // Deleted, retargeted or replaced locally
+            _ => {
+                report.kept.push(path.to_string());
+                return Ok(false);
+            }
+        };
+
+        if !self.dry_run {
+            FsBackend::new(project_root).symlink(new, &native_path(path))?;
+        }
+        list.push(path.to_string());
+        Ok(true)
+    }
+
+    /// Delete files and directories forge created that the structure no longer has
+    fn remove_dropped(
+        &self,
+        project_root: &Path,
+        lockfile: &Lockfile,
+        entries: &[LockEntry],
+        report: &mut UpdateReport,
+    ) -> Result<()> {
+        let still_present = |path: &str| entries.iter().any(|entry| entry.path == path);
+
+        for old in lockfile.entries.iter().rev() {
+            if still_present(&old.path) || old.status != LockEntryStatus::Created {
+                continue;
+            }
+            let full_path = project_path(project_root, &old.path)?;
+
+            match old.kind {
+                LockEntryKind::File => {
+                    let Ok(current) = fs::read(&full_path) else {
+                        continue;
+                    };
+                    if old.hash.as_deref() == Some(hash_bytes(&current).as_str()) {
+                        if !self.dry_run {
+                            fs::remove_file(&full_path)?;
+                        }
+                        report.removed.push(old.path.clone());
+                    } else {
+                        report.kept.push(old.path.clone());
+                    }
+                }
+                LockEntryKind::Symlink => {
+                    let Ok(current) = fs::read_link(&full_path) else {
+                        continue;
+                    };
+                    if old.target.as_deref().map(Path::new) == Some(current.as_path()) {
+                        if !self.dry_run {
+                            fs::remove_file(&full_path)?;
+                        }
+                        report.removed.push(old.path.clone());
+                    } else {
+                        report.kept.push(old.path.clone());
+                    }
+                }
+                LockEntryKind::Directory => {
+                    let empty = fs::read_dir(&full_path).is_ok_and(|mut children| children.next().is_none());
+                    if empty && !self.dry_run {
+                        fs::remove_dir(&full_path)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        if self.dry_run {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+/// Current contents of a file, or `None` if it doesn't exist
+fn read_bytes(path: &Path) -> Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
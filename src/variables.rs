@@ -0,0 +1,245 @@
+//! Typed variable declarations
+//!
+//! Structure files can declare the variables they use in a YAML front matter
+//! block, giving each one a type, default, description, pattern and required
+//! flag:
+//!
+//! ```text
+//! ---
+//! variables:
+//!   use_docker:
+//!     type: bool
+//!     default: false
+//!     description: Include Docker files
+//!   database:
+//!     type: enum
+//!     values: [postgres, mysql]
+//! ---
+//! my-service/
+//! └── src/
+//! ```
+//!
+//! Values always arrive as strings (from `--var`, config or prompts). They are
+//! validated against their declaration before generation and converted to
+//! typed JSON values for rendering, so `{{#if use_docker}}` sees a real boolean.
+
+use crate::{ForgeTreeError, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// The type of a declared variable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VarType {
+    #[default]
+    String,
+    Bool,
+    Int,
+    Enum,
+    /// Comma-separated list of strings
+    List,
+}
+
+impl fmt::Display for VarType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            VarType::String => "string",
+            VarType::Bool => "bool",
+            VarType::Int => "int",
+            VarType::Enum => "enum",
+            VarType::List => "list",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Declaration of a single variable
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VariableDecl {
+    #[serde(rename = "type", default)]
+    pub var_type: VarType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Regular expression every value (or list element) must match in full
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Allowed values for `enum` variables
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub required: bool,
+}
+
+impl VariableDecl {
+    /// The default value in the string form used by `--var`
+    pub fn default_string(&self) -> Option<String> {
+        self.default.as_ref().map(value_to_string)
+    }
+
+    /// Check a raw string value against this declaration
+    pub fn check(&self, value: &str) -> std::result::Result<(), String> {
+        match self.var_type {
+            VarType::String => {}
+            VarType::Bool => {
+                parse_bool(value).ok_or_else(|| format!("'{}' is not a boolean (use true or false)", value))?;
+            }
+            VarType::Int => {
+                value.trim().parse::<i64>().map_err(|_| format!("'{}' is not an integer", value))?;
+            }
+            VarType::Enum => {
+                if !self.values.iter().any(|allowed| allowed == value) {
+                    return Err(format!("'{}' is not one of: {}", value, self.values.join(", ")));
+                }
+            }
+            VarType::List => {}
+        }
+
+        if let Some(pattern) = &self.pattern {
+            let regex = anchored_regex(pattern)?;
+            let candidates = match self.var_type {
+                VarType::List => split_list(value),
+                _ => vec![value.to_string()],
+            };
+            if let Some(bad) = candidates.iter().find(|candidate| !regex.is_match(candidate)) {
+                return Err(format!("'{}' does not match pattern {}", bad, pattern));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Convert a raw string value into a typed JSON value for rendering
+    ///
+    /// Values are expected to have passed [`VariableDecl::check`]; anything
+    /// that doesn't convert falls back to a plain string.
+    pub fn typed_value(&self, value: &str) -> Value {
+        match self.var_type {
+            VarType::Bool => parse_bool(value).map(Value::Bool).unwrap_or_else(|| Value::String(value.to_string())),
+            VarType::Int => value.trim().parse::<i64>().map(Value::from).unwrap_or_else(|_| Value::String(value.to_string())),
+            VarType::List => Value::Array(split_list(value).into_iter().map(Value::String).collect()),
+            VarType::String | VarType::Enum => Value::String(value.to_string()),
+        }
+    }
+}
+
+/// Variable declarations keyed by name
+pub type VariableDecls = BTreeMap<String, VariableDecl>;
+
+/// Check that the declarations themselves are consistent
+///
+/// Patterns must compile, enums need values, and defaults must satisfy their
+/// own declaration.
+pub fn check_declarations(decls: &VariableDecls) -> Result<()> {
+    let mut problems = Vec::new();
+
+    for (name, decl) in decls {
+        if decl.var_type == VarType::Enum && decl.values.is_empty() {
+            problems.push(format!("{}: enum variables need a list of values", name));
+        }
+        if let Some(pattern) = &decl.pattern {
+            if let Err(e) = anchored_regex(pattern) {
+                problems.push(format!("{}: {}", name, e));
+                continue;
+            }
+        }
+        if let Some(default) = decl.default_string() {
+            if let Err(e) = decl.check(&default) {
+                problems.push(format!("{}: invalid default: {}", name, e));
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ForgeTreeError::parse(format!("Invalid variable declarations: {}", problems.join("; "))))
+    }
+}
+
+/// Validate values against their declarations, reporting every problem at once
+///
+/// Required variables must have a value; undeclared values are accepted as-is.
+pub fn validate_values(decls: &VariableDecls, values: &HashMap<String, String>) -> Result<()> {
+    let mut problems = Vec::new();
+
+    for (name, decl) in decls {
+        match values.get(name) {
+            Some(value) => {
+                if let Err(e) = decl.check(value) {
+                    problems.push(format!("{}: {}", name, e));
+                }
+            }
+            None if decl.required => problems.push(format!("{}: a value is required", name)),
+            None => {}
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ForgeTreeError::InvalidVariable(problems.join("; ")))
+    }
+}
+
+/// Build the JSON context used for rendering, typing declared variables
+pub fn template_context(decls: &VariableDecls, values: &HashMap<String, String>) -> Value {
+    let context: Map<String, Value> = values.iter()
+        .map(|(name, value)| {
+            let typed = match decls.get(name) {
+                Some(decl) => decl.typed_value(value),
+                None => Value::String(value.clone()),
+            };
+            (name.clone(), typed)
+        })
+        .collect();
+
+    Value::Object(context)
+}
+
+/// Whether a context value counts as true for `[if var]` conditions
+///
+/// Follows handlebars truthiness, except that strings spelling a false
+/// boolean (`false`, `no`, `0`, ...) are false, so untyped `--var x=false` works.
+pub fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty() && parse_bool(s) != Some(false),
+        Value::Array(elements) => !elements.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "y" | "on" | "1" => Some(true),
+        "false" | "no" | "n" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',')
+        .map(|element| element.trim().to_string())
+        .filter(|element| !element.is_empty())
+        .collect()
+}
+
+fn anchored_regex(pattern: &str) -> std::result::Result<Regex, String> {
+    Regex::new(&format!("^(?:{})$", pattern)).map_err(|e| format!("invalid pattern {}: {}", pattern, e))
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(elements) => elements.iter().map(value_to_string).collect::<Vec<_>>().join(","),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
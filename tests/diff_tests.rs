@@ -0,0 +1,117 @@
+use assert_cmd::Command;
+use forge_tree::diff::{diff, DifferenceKind};
+use forge_tree::parser::ProjectStructure;
+use forge_tree::scanner::Scanner;
+use forge_tree::Generator;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const STRUCTURE: &str = r#"app/
+├── src/
+│   └── main.rs
+└── README.md
+"#;
+
+fn forge_tree(home: &Path) -> Command {
+    let mut command = Command::cargo_bin("forge-tree").unwrap();
+    command.env("HOME", home).env("XDG_CONFIG_HOME", home.join(".config"));
+    command
+}
+
+/// Write `STRUCTURE` to `dir` and create the matching project next to it
+fn setup(dir: &Path) {
+    fs::write(dir.join("structure.txt"), STRUCTURE).unwrap();
+    fs::create_dir_all(dir.join("app/src")).unwrap();
+    fs::write(dir.join("app/src/main.rs"), "").unwrap();
+    fs::write(dir.join("app/README.md"), "").unwrap();
+}
+
+fn compare(structure: &ProjectStructure, root: &Path) -> Vec<DifferenceKind> {
+    let expected = Generator::new().render(structure).unwrap();
+    let scanner = Scanner::new();
+    let actual = scanner.scan(root).unwrap();
+    diff(&expected, &actual, root, scanner.ignore(), true).unwrap()
+        .into_iter()
+        .map(|difference| difference.kind)
+        .collect()
+}
+
+#[test]
+fn check_passes_when_the_directory_matches() {
+    let dir = TempDir::new().unwrap();
+    setup(dir.path());
+
+    forge_tree(dir.path())
+        .current_dir(dir.path())
+        .args(["check", "structure.txt", "app"])
+        .assert()
+        .code(0);
+}
+
+#[test]
+fn check_fails_on_differences_but_diff_does_not() {
+    let dir = TempDir::new().unwrap();
+    setup(dir.path());
+    fs::remove_file(dir.path().join("app/README.md")).unwrap();
+
+    forge_tree(dir.path())
+        .current_dir(dir.path())
+        .args(["diff", "structure.txt", "app"])
+        .assert()
+        .code(0)
+        .stdout(predicates::str::contains("README.md"));
+    forge_tree(dir.path())
+        .current_dir(dir.path())
+        .args(["check", "structure.txt", "app"])
+        .assert()
+        .code(7);
+}
+
+#[test]
+fn check_reports_errors_with_their_own_exit_code() {
+    let dir = TempDir::new().unwrap();
+    setup(dir.path());
+
+    forge_tree(dir.path())
+        .current_dir(dir.path())
+        .args(["check", "missing.txt", "app"])
+        .assert()
+        .failure()
+        .code(predicates::ord::ne(7));
+}
+
+#[test]
+fn compares_files_declared_with_empty_content() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("notes.txt"), "not empty\n").unwrap();
+
+    let empty = ProjectStructure::builder("app").file("notes.txt").content("").build().unwrap();
+    let undeclared = ProjectStructure::builder("app").file("notes.txt").build().unwrap();
+
+    assert_eq!(compare(&empty, dir.path()), [DifferenceKind::ContentMismatch]);
+    assert!(compare(&undeclared, dir.path()).is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn compares_directory_modes() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = TempDir::new().unwrap();
+    let structure = forge_tree::Parser::new().parse("app/\n├── private/  [mode=700]\n└── run.sh  [mode=750]\n").unwrap();
+    Generator::new().generate(&structure, dir.path()).unwrap();
+    let root = dir.path().join("app");
+    assert!(compare(&structure, &root).is_empty());
+
+    fs::set_permissions(root.join("private"), fs::Permissions::from_mode(0o755)).unwrap();
+    let expected = Generator::new().render(&structure).unwrap();
+    let scanner = Scanner::new();
+    let differences = diff(&expected, &scanner.scan(&root).unwrap(), &root, scanner.ignore(), false).unwrap();
+
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    assert_eq!(differences[0].path, "private");
+    assert_eq!(differences[0].kind, DifferenceKind::ModeMismatch);
+    assert_eq!(differences[0].expected_mode.as_deref(), Some("700"));
+    assert_eq!(differences[0].actual_mode.as_deref(), Some("755"));
+}
@@ -0,0 +1,81 @@
+use forge_tree::parser::ProjectStructure;
+use forge_tree::{Generator, Parser};
+use tempfile::TempDir;
+
+#[test]
+fn concurrent_runs_report_the_first_failure_in_tree_order() {
+    // The first failing file is slow to render and the last one fails at once,
+    // so the later error usually comes in first
+    let slow = format!("{}{{{{first_missing 1}}}}", "padding ".repeat(20_000));
+    let mut builder = ProjectStructure::builder("app").file("a_first.txt").template(&slow);
+    for index in 0..32 {
+        builder = builder.file(&format!("b_{:02}.txt", index)).content("ok\n");
+    }
+    let structure = builder.file("c_last.txt").template("{{last_missing 1}}").build().unwrap();
+
+    for _ in 0..5 {
+        let dir = TempDir::new().unwrap();
+        let error = Generator::new().with_jobs(8).generate(&structure, dir.path()).unwrap_err();
+        assert!(error.to_string().contains("first_missing"), "{}", error);
+    }
+}
+
+#[test]
+fn concurrent_runs_write_the_same_files_as_sequential_ones() {
+    let mut builder = ProjectStructure::builder("app");
+    for index in 0..16 {
+        builder = builder.file(&format!("f_{:02}.txt", index)).template(&format!("{{{{name}}}} {}\n", index));
+    }
+    let mut structure = builder.build().unwrap();
+    structure.variables.insert("name".to_string(), "demo".to_string());
+
+    let sequential = Generator::new().render(&structure).unwrap();
+    let concurrent = Generator::new().with_jobs(4).render(&structure).unwrap();
+    assert_eq!(sequential, concurrent);
+}
+
+const SERVICES: &str = "monorepo/
+└── services/
+    └── {{item}}/  [each services]
+        ├── src/
+        │   └── main.rs
+        └── {{@index}}-{{item}}.md
+";
+
+fn planned_paths(input: &str, services: &str) -> forge_tree::Result<Vec<String>> {
+    let mut structure = Parser::new().parse(input)?;
+    structure.variables.insert("services".to_string(), services.to_string());
+    let plan = Generator::new().plan(&structure)?;
+    Ok(plan.entries.iter().map(|entry| entry.path().to_string_lossy().replace('\\', "/")).collect())
+}
+
+#[test]
+fn loops_repeat_the_subtree_for_every_element() {
+    assert_eq!(
+        planned_paths(SERVICES, "auth, billing").unwrap(),
+        [
+            "services",
+            "services/auth",
+            "services/auth/src",
+            "services/auth/src/main.rs",
+            "services/auth/0-auth.md",
+            "services/billing",
+            "services/billing/src",
+            "services/billing/src/main.rs",
+            "services/billing/1-billing.md",
+        ]
+    );
+    assert_eq!(planned_paths(SERVICES, "").unwrap(), ["services"]);
+}
+
+#[test]
+fn loops_reject_elements_rendering_the_same_path() {
+    let error = planned_paths(SERVICES, "auth,auth").unwrap_err();
+    assert!(error.to_string().contains("services/auth"), "{}", error);
+}
+
+#[test]
+fn loops_need_their_list() {
+    let structure = Parser::new().parse(SERVICES).unwrap();
+    assert!(Generator::new().plan(&structure).is_err());
+}
@@ -0,0 +1,117 @@
+#![cfg(unix)]
+
+use assert_cmd::Command;
+use forge_tree::hooks::{FailurePolicy, Hook, HookAction};
+use forge_tree::parser::ProjectStructure;
+use forge_tree::Generator;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn forge_tree(home: &Path) -> Command {
+    let mut command = Command::cargo_bin("forge-tree").unwrap();
+    command.env("HOME", home).env_remove("FORGE_TREE_CONFIG");
+    command
+}
+
+fn structure_with_hook(command: &str, name: &str) -> ProjectStructure {
+    let mut structure = ProjectStructure::builder("app").file("README.md").build().unwrap();
+    structure.hooks.post_generate.push(Hook::run(command));
+    structure.variables.insert("name".to_string(), name.to_string());
+    structure
+}
+
+#[test]
+fn variables_reach_hooks_through_the_environment() {
+    let dir = TempDir::new().unwrap();
+    let structure = structure_with_hook("printf '%s' \"$FORGE_TREE_VAR_NAME\" > name.txt", "demo");
+
+    Generator::new().with_hooks(true).generate(&structure, dir.path()).unwrap();
+
+    assert_eq!(fs::read_to_string(dir.path().join("app/name.txt")).unwrap(), "demo");
+}
+
+#[test]
+fn variable_values_are_not_run_as_commands() {
+    let dir = TempDir::new().unwrap();
+    let structure = structure_with_hook(
+        "printf '%s' \"$FORGE_TREE_VAR_NAME\" > name.txt",
+        "x; touch injected",
+    );
+
+    Generator::new().with_hooks(true).generate(&structure, dir.path()).unwrap();
+
+    assert_eq!(fs::read_to_string(dir.path().join("app/name.txt")).unwrap(), "x; touch injected");
+    assert!(!dir.path().join("app/injected").exists());
+}
+
+#[test]
+fn commands_are_not_rendered_as_templates() {
+    let dir = TempDir::new().unwrap();
+    let structure = structure_with_hook("printf '%s' '{{name}}' > name.txt", "x'; touch injected; '");
+
+    Generator::new().with_hooks(true).generate(&structure, dir.path()).unwrap();
+
+    assert_eq!(fs::read_to_string(dir.path().join("app/name.txt")).unwrap(), "{{name}}");
+    assert!(!dir.path().join("app/injected").exists());
+}
+
+#[test]
+fn scripts_run_without_going_through_the_shell_parser() {
+    let dir = TempDir::new().unwrap();
+    let templates = dir.path().join("templates");
+    fs::create_dir(&templates).unwrap();
+    fs::write(templates.join("it's $HOME.sh"), "touch ran\n").unwrap();
+
+    let mut structure = ProjectStructure::builder("app").build().unwrap();
+    structure.hooks.post_generate.push(Hook {
+        action: HookAction::Script("it's $HOME.sh".into()),
+        on_failure: FailurePolicy::Abort,
+        source: Some(templates.join("structure.tree")),
+    });
+    let hook = &structure.hooks.post_generate[0];
+    assert!(hook.command_line().ends_with("it's $HOME.sh"), "{}", hook.command_line());
+
+    Generator::new().with_hooks(true).generate(&structure, dir.path()).unwrap();
+
+    assert!(dir.path().join("app/ran").is_file());
+}
+
+#[test]
+fn failing_hooks_exit_with_the_hook_code() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join(".config/forge-tree")).unwrap();
+    fs::write(
+        dir.path().join(".config/forge-tree/config.yaml"),
+        format!("trusted:\n  - {}\n", dir.path().display()),
+    ).unwrap();
+    fs::write(dir.path().join("app.tree"), "---\nhooks:\n  post_generate:\n    - exit 3\n---\napp/\n└── README.md\n").unwrap();
+
+    forge_tree(dir.path())
+        .current_dir(dir.path())
+        .args(["forge", "app.tree", "--no-input"])
+        .assert()
+        .code(8)
+        .stderr(predicates::str::contains("`exit 3` failed"));
+    assert!(dir.path().join("app/README.md").is_file());
+}
+
+#[test]
+fn untrusted_hooks_are_refused_without_a_terminal() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("app.tree"), "---\nhooks:\n  post_generate:\n    - touch ran\n---\napp/\n└── README.md\n").unwrap();
+
+    forge_tree(dir.path())
+        .current_dir(dir.path())
+        .args(["forge", "app.tree", "--no-input"])
+        .assert()
+        .code(8);
+    assert!(!dir.path().join("app").exists());
+
+    forge_tree(dir.path())
+        .current_dir(dir.path())
+        .args(["forge", "app.tree", "--no-input", "--no-hooks"])
+        .assert()
+        .success();
+    assert!(!dir.path().join("app/ran").exists());
+}
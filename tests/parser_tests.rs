@@ -0,0 +1,231 @@
+use forge_tree::parser::{ItemType, ProjectStructure, TreeWriter};
+use forge_tree::Parser;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn paths(input: &str) -> Vec<String> {
+    let structure = Parser::new().parse(input).unwrap();
+    structure.iter().map(|item| item.path.clone()).collect()
+}
+
+#[test]
+fn nests_entries_in_tree_layout() {
+    let input = "app/
+├── src/
+│   ├── main.rs
+│   └── lib.rs
+└── docs/
+    └── guide/
+        └── intro.md
+";
+    assert_eq!(
+        paths(input),
+        ["src", "src/main.rs", "src/lib.rs", "docs", "docs/guide", "docs/guide/intro.md"]
+    );
+}
+
+#[test]
+fn nests_entries_in_compact_layout() {
+    let input = "app/
+├── src/
+│ ├── main.rs
+│ └── bin/
+│   └── cli.rs
+└── README.md
+";
+    assert_eq!(paths(input), ["src", "src/main.rs", "src/bin", "src/bin/cli.rs", "README.md"]);
+}
+
+#[test]
+fn children_of_last_entry_stay_nested() {
+    let structure = Parser::new().parse("app/\n└── src/\n    └── main.rs\n").unwrap();
+    assert_eq!(structure.items.len(), 1);
+    let src = structure.find("src").unwrap();
+    assert_eq!(src.item_type, ItemType::Directory);
+    assert_eq!(src.children[0].path, "src/main.rs");
+}
+
+#[test]
+fn parse_errors_carry_their_line() {
+    let error = Parser::new().parse("app/\n├── src/\n│   └── main.rs  [bogus]\n").unwrap_err();
+    assert_eq!(error.kind(), "parse");
+    assert_eq!(error.line(), Some(3));
+}
+
+#[test]
+fn colons_in_messages_are_not_locations() {
+    let error = Parser::new().parse("app/\n└── at_12:30/  [each]\n").unwrap_err();
+    assert_eq!(error.line(), Some(2));
+
+    let error = forge_tree::ForgeTreeError::parse("Invalid ignore pattern 'a:12'");
+    assert_eq!(error.line(), None);
+}
+
+fn modes(structure: &ProjectStructure) -> Vec<(String, Option<u32>)> {
+    structure.iter().map(|item| (item.path.clone(), item.mode)).collect()
+}
+
+#[test]
+fn reads_modes_and_executable_marks() {
+    let input = "app/
+├── bin/  [mode=750]
+│   └── run*
+├── deploy.sh  [mode=0700]
+└── README.md
+";
+    let structure = Parser::new().parse(input).unwrap();
+
+    assert_eq!(structure.find("bin/run").unwrap().item_type, ItemType::File);
+    assert_eq!(
+        modes(&structure),
+        [
+            ("bin".to_string(), Some(0o750)),
+            ("bin/run".to_string(), Some(0o755)),
+            ("deploy.sh".to_string(), Some(0o700)),
+            ("README.md".to_string(), None),
+        ]
+    );
+    assert!(Parser::new().parse("app/\n└── run.sh  [mode=999]\n").is_err());
+}
+
+#[test]
+fn modes_survive_a_round_trip() {
+    let input = "app/
+├── bin/  [mode=750]
+│   ├── run*
+│   └── tool.py  [mode=755]
+├── scripts/  [mode=755]
+├── deploy.sh  [mode=700]
+└── README.md
+";
+    let structure = Parser::new().parse(input).unwrap();
+
+    let written = TreeWriter::new().write(&structure).unwrap();
+    assert!(written.contains("tool.py*") && written.contains("scripts/  [mode=755]"), "{}", written);
+
+    let reparsed = Parser::new().parse(&written).unwrap();
+    assert_eq!(modes(&reparsed), modes(&structure));
+    assert_eq!(TreeWriter::new().write(&reparsed).unwrap(), written);
+}
+
+#[test]
+fn annotated_leaves_without_an_extension_are_files() {
+    let input = "app/
+├── Dockerfile  [if docker]
+├── logs/  [mode=700]
+├── cache  [unless ci]
+│   └── keep.txt
+└── vendor
+";
+    let structure = Parser::new().parse(input).unwrap();
+    let kind = |path: &str| structure.find(path).unwrap().item_type.clone();
+
+    assert_eq!(kind("Dockerfile"), ItemType::File);
+    assert_eq!(kind("logs"), ItemType::Directory);
+    assert_eq!(kind("cache"), ItemType::Directory);
+    assert_eq!(kind("vendor"), ItemType::Directory);
+}
+
+/// Write `files` (name, contents) into `dir`
+fn write_files(dir: &Path, files: &[(&str, &str)]) {
+    for (name, contents) in files {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+}
+
+fn parse_file(path: &Path) -> forge_tree::Result<ProjectStructure> {
+    Parser::new().parse_file(path.to_str().unwrap())
+}
+
+#[test]
+fn includes_splice_entries_in_place() {
+    let dir = TempDir::new().unwrap();
+    write_files(dir.path(), &[
+        ("app/main.tree", "app/\n├── ci/\n│   └── @include ../shared/ci.tree\n└── src/\n"),
+        ("shared/ci.tree", "ci/\n├── build.yml\n└── deploy.yml\n"),
+    ]);
+
+    let structure = parse_file(&dir.path().join("app/main.tree")).unwrap();
+
+    let paths: Vec<String> = structure.iter().map(|item| item.path.clone()).collect();
+    assert_eq!(paths, ["ci", "ci/build.yml", "ci/deploy.yml", "src"]);
+}
+
+#[test]
+fn include_cycles_report_the_whole_chain() {
+    let dir = TempDir::new().unwrap();
+    write_files(dir.path(), &[
+        ("a.tree", "app/\n└── @include b.tree\n"),
+        ("b.tree", "b/\n├── b.txt\n└── @include a.tree\n"),
+    ]);
+
+    let error = parse_file(&dir.path().join("a.tree")).unwrap_err();
+
+    assert_eq!(error.kind(), "include");
+    let message = error.to_string();
+    assert!(message.contains("a.tree:2 → ") && message.contains("b.tree:3"), "{}", message);
+    assert_eq!(error.line(), Some(2));
+    assert!(error.file().unwrap().ends_with("a.tree"));
+}
+
+#[test]
+fn errors_in_included_files_name_the_include_stack() {
+    let dir = TempDir::new().unwrap();
+    write_files(dir.path(), &[
+        ("a.tree", "app/\n├── src/\n└── @include b.tree\n"),
+        ("b.tree", "b/\n└── @include c.tree\n"),
+        ("c.tree", "c/\n└── broken.txt  [bogus]\n"),
+    ]);
+
+    let error = parse_file(&dir.path().join("a.tree")).unwrap_err();
+
+    let message = error.to_string();
+    assert!(message.contains("c.tree") && message.contains("a.tree:3 → ") && message.contains("b.tree:2"), "{}", message);
+    assert_eq!(error.line(), Some(3));
+
+    let error = Parser::new().parse("app/\n└── @include b.tree\n").unwrap_err();
+    assert_eq!(error.line(), Some(2));
+}
+
+#[test]
+fn extends_overlays_the_base() {
+    let dir = TempDir::new().unwrap();
+    write_files(dir.path(), &[
+        ("base-service.tree", "service/\n├── src/\n│   └── main.rs\n├── legacy/\n│   └── old.rs\n└── docs/\n    └── intro.md\n"),
+        ("payments.tree", "---\nextends: base-service\n---\npayments/\n├── src/\n│   └── api.rs\n├── -legacy/\n└── docs/  [replace]\n    └── payments.md\n"),
+    ]);
+
+    let structure = parse_file(&dir.path().join("payments.tree")).unwrap();
+
+    assert_eq!(structure.root, "payments");
+    let paths: Vec<String> = structure.iter().map(|item| item.path.clone()).collect();
+    assert_eq!(paths, ["src", "src/main.rs", "src/api.rs", "docs", "docs/payments.md"]);
+}
+
+#[test]
+fn extends_rejects_removing_what_the_base_lacks() {
+    let dir = TempDir::new().unwrap();
+    write_files(dir.path(), &[
+        ("base.tree", "service/\n└── src/\n"),
+        ("child.tree", "---\nextends: base\n---\nchild/\n└── -docs/\n"),
+    ]);
+
+    let error = parse_file(&dir.path().join("child.tree")).unwrap_err();
+
+    assert!(error.file().unwrap().ends_with("child.tree"), "{:?}", error.file());
+    assert_eq!(error.line(), Some(5));
+}
+
+#[test]
+fn extends_cycles_are_rejected() {
+    let dir = TempDir::new().unwrap();
+    write_files(dir.path(), &[
+        ("a.tree", "---\nextends: b\n---\na/\n"),
+        ("b.tree", "---\nextends: a\n---\nb/\n"),
+    ]);
+
+    assert!(parse_file(&dir.path().join("a.tree")).is_err());
+}
@@ -0,0 +1,78 @@
+use assert_cmd::Command;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const STRUCTURE: &str = "app/
+├── src/
+│   └── main.rs
+├── Dockerfile  [if docker]
+└── README.md
+";
+
+fn forge_tree(home: &Path) -> Command {
+    let mut command = Command::cargo_bin("forge-tree").unwrap();
+    command.env("HOME", home).env("XDG_CONFIG_HOME", home.join(".config"));
+    command
+}
+
+/// Forge `STRUCTURE` with `--progress json` and parse every line of output
+fn json_events(dir: &Path, extra: &[&str]) -> Vec<Value> {
+    fs::write(dir.join("structure.txt"), STRUCTURE).unwrap();
+    let output = forge_tree(dir)
+        .current_dir(dir)
+        .args(["forge", "structure.txt", "--progress", "json", "--no-input"])
+        .args(extra)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    String::from_utf8(output.stdout).unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|e| panic!("{}: {}", e, line)))
+        .collect()
+}
+
+#[test]
+fn json_progress_prints_one_object_per_event() {
+    let dir = TempDir::new().unwrap();
+
+    let events = json_events(dir.path(), &[]);
+
+    let names: Vec<_> = events.iter().map(|event| event["event"].as_str().unwrap()).collect();
+    assert_eq!(names.first(), Some(&"started"));
+    assert_eq!(names.last(), Some(&"finished"));
+
+    let started = &events[0];
+    assert_eq!(started["dry_run"], false);
+    assert!(started["total"].as_u64().unwrap() >= 4, "{}", started);
+
+    let main = events.iter()
+        .find(|event| event["path"].as_str().is_some_and(|path| path.ends_with("main.rs")))
+        .unwrap();
+    assert_eq!(main["event"], "item_created");
+    assert_eq!(main["kind"], "file");
+
+    let excluded = events.iter().find(|event| event["event"] == "item_skipped").unwrap();
+    assert_eq!(excluded["reason"], "excluded");
+    assert_eq!(excluded["condition"], "if docker");
+    assert_eq!(excluded["items"], 1);
+
+    let finished = events.last().unwrap();
+    assert_eq!((finished["created"].as_u64(), finished["excluded"].as_u64()), (Some(2), Some(1)));
+}
+
+#[test]
+fn json_progress_reports_existing_files() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("app")).unwrap();
+    fs::write(dir.path().join("app/README.md"), "mine\n").unwrap();
+
+    let events = json_events(dir.path(), &["--on-conflict", "backup"]);
+
+    let replaced = events.iter().find(|event| event["event"] == "item_replaced").unwrap();
+    assert!(replaced["path"].as_str().unwrap().ends_with("README.md"), "{}", replaced);
+    assert!(replaced["backup"].as_str().unwrap().ends_with("README.md.orig"), "{}", replaced);
+    assert_eq!(events.last().unwrap()["backed_up"], 1);
+}
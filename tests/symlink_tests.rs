@@ -0,0 +1,73 @@
+use forge_tree::parser::{ItemType, ProjectStructure, TreeWriter};
+use forge_tree::{Generator, Parser};
+
+fn plan_error(input: &str) -> Option<String> {
+    let structure = Parser::new().parse(input).unwrap();
+    Generator::new().plan(&structure).err().map(|error| error.to_string())
+}
+
+fn entries(structure: &ProjectStructure) -> Vec<(String, ItemType)> {
+    structure.iter().map(|item| (item.path.clone(), item.item_type.clone())).collect()
+}
+
+#[test]
+fn parses_links_with_their_target() {
+    let structure = Parser::new().parse("app/\n├── docs/\n└── latest -> docs/v2\n").unwrap();
+
+    let link = structure.find("latest").unwrap();
+    assert_eq!(link.item_type, ItemType::Symlink { target: "docs/v2".to_string() });
+}
+
+#[test]
+fn writes_links_back() {
+    let input = "app/\n├── docs/\n│   └── current -> ../docs\n└── latest -> docs/v2\n";
+    let structure = Parser::new().parse(input).unwrap();
+
+    let written = TreeWriter::new().write(&structure).unwrap();
+
+    assert!(written.contains("current -> ../docs"), "{}", written);
+    let reparsed = Parser::new().parse(&written).unwrap();
+    assert_eq!(entries(&reparsed), entries(&structure));
+}
+
+#[test]
+fn allows_targets_inside_the_root() {
+    assert_eq!(plan_error("app/\n├── docs/\n│   └── up -> ../README.md\n└── README.md\n"), None);
+    assert_eq!(plan_error("app/\n└── shell -> /bin/sh\n"), None);
+}
+
+#[test]
+fn rejects_targets_above_the_root() {
+    assert!(plan_error("app/\n└── up -> ../secret\n").is_some());
+    assert!(plan_error("app/\n└── docs/\n    └── up -> ../../secret\n").is_some());
+    assert!(plan_error("app/\n└── sneaky -> docs/../../secret\n").is_some());
+}
+
+#[test]
+fn rejects_targets_that_escape_through_other_links() {
+    let error = plan_error("app/\n└── sub/\n    ├── a -> ..\n    └── b -> a/../..\n").unwrap();
+    assert!(error.contains("sub/b"), "{}", error);
+
+    let error = plan_error("app/\n├── a -> sub/deeper/../..\n├── b -> a/..\n└── sub/\n").unwrap();
+    assert!(error.contains("'b'"), "{}", error);
+}
+
+#[test]
+fn follows_links_that_stay_inside() {
+    assert_eq!(plan_error("app/\n└── sub/\n    ├── a -> ..\n    └── b -> a/sub\n"), None);
+    // A loop of links can't be resolved, but doesn't lead anywhere either
+    assert_eq!(plan_error("app/\n├── a -> b/x\n└── b -> a/x\n"), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn creates_links_without_following_them() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let structure = Parser::new().parse("app/\n├── docs/\n└── latest -> docs\n").unwrap();
+
+    Generator::new().generate(&structure, dir.path()).unwrap();
+
+    let link = dir.path().join("app/latest");
+    assert_eq!(std::fs::read_link(&link).unwrap(), std::path::Path::new("docs"));
+    assert!(link.is_dir());
+}
@@ -0,0 +1,129 @@
+use forge_tree::generator::ConflictPolicy;
+use forge_tree::lockfile::LOCKFILE_NAME;
+use forge_tree::undo::Undo;
+use forge_tree::{Generator, Parser};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+const STRUCTURE: &str = r#"app/
+├── src/
+│   └── main.rs
+└── README.md
+"#;
+
+/// Forge `STRUCTURE` into `dir` with a lockfile and return the project root
+fn forge(dir: &Path, policy: ConflictPolicy) -> PathBuf {
+    let mut structure = Parser::new().parse(STRUCTURE).unwrap();
+    structure.items[0].children[0].content = Some("fn main() {}\n".to_string());
+    structure.items[1].content = Some("# app\n".to_string());
+    Generator::new()
+        .with_lockfile(true)
+        .with_conflict_policy(policy)
+        .generate(&structure, dir)
+        .unwrap();
+    dir.join("app")
+}
+
+#[test]
+fn removes_everything_a_forge_created() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), ConflictPolicy::Error);
+
+    let report = Undo::new().run(&root).unwrap();
+
+    assert_eq!(report.removed_files, ["README.md", "src/main.rs"]);
+    assert_eq!(report.removed_directories, ["src"]);
+    assert!(report.removed_root);
+    assert!(!root.exists());
+}
+
+#[test]
+fn dry_run_touches_nothing() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), ConflictPolicy::Error);
+
+    let report = Undo::new().with_dry_run(true).run(&root).unwrap();
+
+    assert!(report.removed_root);
+    assert!(root.join("src/main.rs").is_file());
+    assert!(root.join(LOCKFILE_NAME).is_file());
+}
+
+#[test]
+fn keeps_modified_files_and_the_manifest() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), ConflictPolicy::Error);
+    fs::write(root.join("README.md"), "# my notes\n").unwrap();
+
+    let report = Undo::new().run(&root).unwrap();
+
+    assert_eq!(report.modified, ["README.md"]);
+    assert_eq!(report.removed_files, ["src/main.rs"]);
+    assert_eq!(fs::read_to_string(root.join("README.md")).unwrap(), "# my notes\n");
+    assert!(root.join(LOCKFILE_NAME).is_file(), "the manifest is kept for a --force run");
+}
+
+#[test]
+fn force_removes_modified_files() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), ConflictPolicy::Error);
+    fs::write(root.join("README.md"), "# my notes\n").unwrap();
+
+    let report = Undo::new().with_force(true).run(&root).unwrap();
+
+    assert!(report.modified.is_empty());
+    assert!(report.removed_files.contains(&"README.md".to_string()));
+    assert!(!root.exists());
+}
+
+#[test]
+fn restores_backed_up_files() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("app")).unwrap();
+    fs::write(dir.path().join("app/README.md"), "original\n").unwrap();
+    let root = forge(dir.path(), ConflictPolicy::Backup);
+    assert!(root.join("README.md.orig").is_file());
+
+    let report = Undo::new().run(&root).unwrap();
+
+    assert_eq!(report.restored, ["README.md"]);
+    assert_eq!(fs::read_to_string(root.join("README.md")).unwrap(), "original\n");
+    assert!(!root.join("README.md.orig").exists());
+    assert!(!report.removed_root, "the root existed before the forge");
+}
+
+#[test]
+fn rejects_manifest_paths_outside_the_project() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), ConflictPolicy::Error);
+    let victim = dir.path().join("victim.txt");
+    fs::write(&victim, "# app\n").unwrap();
+
+    // Same contents as README.md, so the hash check alone would not protect it
+    let lock_path = root.join(LOCKFILE_NAME);
+    let tampered = fs::read_to_string(&lock_path).unwrap().replace("\"README.md\"", "\"../victim.txt\"");
+    fs::write(&lock_path, tampered).unwrap();
+
+    for undo in [Undo::new(), Undo::new().with_force(true)] {
+        let error = undo.run(&root).unwrap_err();
+        assert_eq!(error.kind(), "parse");
+    }
+    assert!(victim.is_file());
+    assert!(root.join("src/main.rs").is_file(), "nothing is undone from a rejected manifest");
+}
+
+#[test]
+fn rejects_absolute_backup_paths() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("app")).unwrap();
+    fs::write(dir.path().join("app/README.md"), "original\n").unwrap();
+    let root = forge(dir.path(), ConflictPolicy::Backup);
+
+    let lock_path = root.join(LOCKFILE_NAME);
+    let tampered = fs::read_to_string(&lock_path).unwrap().replace("\"README.md.orig\"", "\"/etc/passwd\"");
+    fs::write(&lock_path, tampered).unwrap();
+
+    assert!(Undo::new().run(&root).is_err());
+    assert!(root.join("README.md.orig").is_file());
+}
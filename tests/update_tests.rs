@@ -0,0 +1,284 @@
+use forge_tree::lockfile::{LockEntryStatus, Lockfile, LOCKFILE_NAME};
+use forge_tree::parser::ProjectStructure;
+use forge_tree::update::{ConflictStyle, Update};
+use forge_tree::Generator;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn structure(notes: &str) -> ProjectStructure {
+    ProjectStructure::builder("app")
+        .file("notes.txt").content(notes)
+        .file("README.md").content("# app\n")
+        .build()
+        .unwrap()
+}
+
+fn forge(dir: &Path, structure: &ProjectStructure) -> PathBuf {
+    Generator::new().with_lockfile(true).generate(structure, dir).unwrap();
+    dir.join("app")
+}
+
+fn read(path: PathBuf) -> String {
+    fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn replaces_files_that_were_not_modified() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), &structure("one\ntwo\nthree\n"));
+
+    let report = Update::new().run(&Generator::new(), &structure("one\n2\nthree\n"), &root).unwrap();
+
+    assert_eq!(report.updated, ["notes.txt"]);
+    assert_eq!(read(root.join("notes.txt")), "one\n2\nthree\n");
+    let lockfile = Lockfile::load(&root).unwrap();
+    assert_eq!(lockfile.entry("notes.txt").unwrap().content.as_deref(), Some("one\n2\nthree\n"));
+}
+
+#[test]
+fn merges_separate_changes() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), &structure("one\ntwo\nthree\nfour\nfive\n"));
+    fs::write(root.join("notes.txt"), "ONE\ntwo\nthree\nfour\nfive\n").unwrap();
+
+    let report = Update::new().run(&Generator::new(), &structure("one\ntwo\nthree\nfour\nFIVE\n"), &root).unwrap();
+
+    assert_eq!(report.merged, ["notes.txt"]);
+    assert_eq!(read(root.join("notes.txt")), "ONE\ntwo\nthree\nfour\nFIVE\n");
+}
+
+#[test]
+fn writes_conflict_markers() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), &structure("one\ntwo\nthree\n"));
+    fs::write(root.join("notes.txt"), "one\nmine\nthree\n").unwrap();
+
+    let report = Update::new().run(&Generator::new(), &structure("one\ntheirs\nthree\n"), &root).unwrap();
+
+    assert_eq!(report.conflicts, ["notes.txt"]);
+    let notes = read(root.join("notes.txt"));
+    assert!(notes.contains("<<<<<<<") && notes.contains("mine") && notes.contains("theirs"), "{}", notes);
+}
+
+#[test]
+fn writes_rejects_instead_of_markers() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), &structure("one\ntwo\nthree\n"));
+    fs::write(root.join("notes.txt"), "one\nmine\nthree\n").unwrap();
+
+    let report = Update::new()
+        .with_conflict_style(ConflictStyle::Reject)
+        .run(&Generator::new(), &structure("one\ntheirs\nthree\n"), &root)
+        .unwrap();
+
+    assert_eq!(report.conflicts, ["notes.txt"]);
+    assert_eq!(read(root.join("notes.txt")), "one\nmine\nthree\n");
+    assert!(read(root.join("notes.txt.rej")).contains("+theirs"));
+}
+
+#[test]
+fn rejected_changes_are_offered_again() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), &structure("one\ntwo\nthree\n"));
+    fs::write(root.join("notes.txt"), "one\nmine\nthree\n").unwrap();
+    let update = Update::new().with_conflict_style(ConflictStyle::Reject);
+
+    for _ in 0..2 {
+        fs::remove_file(root.join("notes.txt.rej")).ok();
+        let report = update.run(&Generator::new(), &structure("one\ntheirs\nthree\n"), &root).unwrap();
+
+        assert_eq!(report.conflicts, ["notes.txt"]);
+        assert_eq!(read(root.join("notes.txt")), "one\nmine\nthree\n");
+        assert!(read(root.join("notes.txt.rej")).contains("+theirs"));
+        let lockfile = Lockfile::load(&root).unwrap();
+        assert_eq!(lockfile.entry("notes.txt").unwrap().content.as_deref(), Some("one\ntwo\nthree\n"));
+    }
+}
+
+#[test]
+fn leaves_files_that_are_no_longer_text_alone() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), &structure("one\n"));
+    let binary = [0xff, 0xfe, b'o', b'n', b'e', 0x00, b'\n'];
+    fs::write(root.join("notes.txt"), binary).unwrap();
+
+    let report = Update::new().run(&Generator::new(), &structure("two\n"), &root).unwrap();
+
+    assert_eq!(report.conflicts, ["notes.txt"]);
+    assert_eq!(fs::read(root.join("notes.txt")).unwrap(), binary);
+}
+
+#[test]
+fn removes_dropped_files_unless_modified() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), &structure("notes\n"));
+    fs::write(root.join("notes.txt"), "my notes\n").unwrap();
+
+    let only_readme = ProjectStructure::builder("app").file("README.md").content("# app\n").build().unwrap();
+    let dropped_both = ProjectStructure::builder("app").build().unwrap();
+
+    let report = Update::new().run(&Generator::new(), &only_readme, &root).unwrap();
+    assert_eq!(report.kept, ["notes.txt"]);
+    assert!(root.join("notes.txt").is_file());
+
+    let report = Update::new().run(&Generator::new(), &dropped_both, &root).unwrap();
+    assert_eq!(report.removed, ["README.md"]);
+    assert!(!root.join("README.md").exists());
+}
+
+#[test]
+fn does_not_recreate_files_deleted_locally() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), &structure("one\n"));
+    fs::remove_file(root.join("notes.txt")).unwrap();
+
+    let report = Update::new().run(&Generator::new(), &structure("two\n"), &root).unwrap();
+
+    assert_eq!(report.kept, ["notes.txt"]);
+    assert!(!root.join("notes.txt").exists());
+}
+
+#[test]
+fn dry_run_changes_nothing() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), &structure("one\n"));
+    let lock_before = read(root.join(LOCKFILE_NAME));
+
+    let report = Update::new().with_dry_run(true).run(&Generator::new(), &structure("two\n"), &root).unwrap();
+
+    assert_eq!(report.updated, ["notes.txt"]);
+    assert_eq!(read(root.join("notes.txt")), "one\n");
+    assert_eq!(read(root.join(LOCKFILE_NAME)), lock_before);
+}
+
+#[test]
+fn keeps_status_of_created_files() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), &structure("one\n"));
+
+    Update::new().run(&Generator::new(), &structure("two\n"), &root).unwrap();
+
+    let lockfile = Lockfile::load(&root).unwrap();
+    assert_eq!(lockfile.entry("notes.txt").unwrap().status, LockEntryStatus::Created);
+}
+
+#[test]
+fn rejects_manifest_paths_outside_the_project() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), &structure("notes\n"));
+    let victim = dir.path().join("victim.txt");
+    fs::write(&victim, "notes\n").unwrap();
+
+    let lock_path = root.join(LOCKFILE_NAME);
+    let tampered = read(lock_path.clone()).replace("\"notes.txt\"", "\"../victim.txt\"");
+    fs::write(&lock_path, tampered).unwrap();
+
+    let only_readme = ProjectStructure::builder("app").file("README.md").content("# app\n").build().unwrap();
+    assert!(Update::new().run(&Generator::new(), &only_readme, &root).is_err());
+    assert_eq!(read(victim), "notes\n");
+}
+
+#[test]
+fn leaves_files_kept_at_forge_time_alone() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("app")).unwrap();
+    fs::write(dir.path().join("app/notes.txt"), "my own notes\n").unwrap();
+    Generator::new()
+        .with_lockfile(true)
+        .with_conflict_policy(forge_tree::generator::ConflictPolicy::Skip)
+        .generate(&structure("one\n"), dir.path())
+        .unwrap();
+    let root = dir.path().join("app");
+
+    for _ in 0..2 {
+        let report = Update::new().run(&Generator::new(), &structure("two\n"), &root).unwrap();
+        assert_eq!(report.kept, ["notes.txt"]);
+        assert!(report.conflicts.is_empty());
+    }
+
+    assert_eq!(read(root.join("notes.txt")), "my own notes\n");
+    assert!(!root.join("notes.txt.rej").exists());
+    let entry = Lockfile::load(&root).unwrap().entry("notes.txt").cloned().unwrap();
+    assert_eq!(entry.status, LockEntryStatus::Skipped);
+    assert_eq!(entry.content, None);
+}
+
+#[test]
+fn leaves_files_that_appeared_since_alone() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), &structure("one\n"));
+    fs::write(root.join("LICENSE"), "mine\n").unwrap();
+
+    let with_license = ProjectStructure::builder("app")
+        .file("notes.txt").content("one\n")
+        .file("README.md").content("# app\n")
+        .file("LICENSE").content("MIT\n")
+        .build()
+        .unwrap();
+    let report = Update::new().run(&Generator::new(), &with_license, &root).unwrap();
+
+    assert_eq!(report.kept, ["LICENSE"]);
+    assert_eq!(read(root.join("LICENSE")), "mine\n");
+    let lockfile = Lockfile::load(&root).unwrap();
+    assert_eq!(lockfile.entry("LICENSE").unwrap().status, LockEntryStatus::Skipped);
+}
+
+fn linked(target: &str) -> ProjectStructure {
+    let source = format!("app/\n├── a.txt\n├── b.txt\n└── latest -> {}\n", target);
+    forge_tree::Parser::new().parse(&source).unwrap()
+}
+
+#[cfg(unix)]
+#[test]
+fn retargets_links_forge_created() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), &linked("a.txt"));
+
+    let report = Update::new().run(&Generator::new(), &linked("b.txt"), &root).unwrap();
+
+    assert_eq!(report.updated, ["latest"]);
+    assert_eq!(fs::read_link(root.join("latest")).unwrap(), Path::new("b.txt"));
+    let entry = Lockfile::load(&root).unwrap().entry("latest").cloned().unwrap();
+    assert_eq!(entry.status, LockEntryStatus::Created);
+    assert_eq!(entry.target.as_deref(), Some("b.txt"));
+}
+
+#[cfg(unix)]
+#[test]
+fn leaves_links_retargeted_locally_alone() {
+    let dir = TempDir::new().unwrap();
+    let root = forge(dir.path(), &linked("a.txt"));
+    fs::remove_file(root.join("latest")).unwrap();
+    std::os::unix::fs::symlink("mine.txt", root.join("latest")).unwrap();
+
+    let report = Update::new().run(&Generator::new(), &linked("b.txt"), &root).unwrap();
+
+    assert_eq!(report.kept, ["latest"]);
+    assert_eq!(fs::read_link(root.join("latest")).unwrap(), Path::new("mine.txt"));
+    assert_eq!(Lockfile::load(&root).unwrap().entry("latest").unwrap().status, LockEntryStatus::Created);
+}
+
+#[cfg(unix)]
+#[test]
+fn leaves_links_kept_at_forge_time_alone() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("app")).unwrap();
+    std::os::unix::fs::symlink("mine.txt", dir.path().join("app/latest")).unwrap();
+    Generator::new()
+        .with_lockfile(true)
+        .with_conflict_policy(forge_tree::generator::ConflictPolicy::Skip)
+        .generate(&linked("a.txt"), dir.path())
+        .unwrap();
+    let root = dir.path().join("app");
+
+    for _ in 0..2 {
+        let report = Update::new().run(&Generator::new(), &linked("b.txt"), &root).unwrap();
+        assert_eq!(report.kept, ["latest"]);
+    }
+
+    assert_eq!(fs::read_link(root.join("latest")).unwrap(), Path::new("mine.txt"));
+    let entry = Lockfile::load(&root).unwrap().entry("latest").cloned().unwrap();
+    assert_eq!(entry.status, LockEntryStatus::Skipped);
+    assert_eq!(entry.target, None);
+}